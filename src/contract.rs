@@ -1,12 +1,33 @@
 use crate::error::ContractError;
+use crate::execute::accept_operator::exec_accept_operator;
+use crate::execute::alter_schema::exec_alter_schema;
+use crate::execute::batch::exec_batch;
+use crate::execute::cancel_operator::exec_cancel_operator_proposal;
+use crate::execute::continue_job::exec_continue_job;
 use crate::execute::create::exec_create;
 use crate::execute::delete::exec_delete;
+use crate::execute::grant_roles::exec_grant_roles;
+use crate::execute::propose_operator::exec_propose_operator;
+use crate::execute::prune::exec_prune;
+use crate::execute::purge::exec_purge;
+use crate::execute::rebuild_index::exec_rebuild_index;
+use crate::execute::restore::exec_restore;
+use crate::execute::revert::exec_revert;
+use crate::execute::revoke_roles::exec_revoke_roles;
+use crate::execute::set_metadata::exec_set_metadata;
+use crate::execute::transfer_ownership::exec_transfer_ownership;
 use crate::execute::update::exec_update;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::query::aggregate::query_aggregate;
+use crate::query::history::query_history;
 use crate::query::info::query_info;
+use crate::query::job::query_job;
+use crate::query::pending_operator::query_pending_operator;
+use crate::query::permissions::query_permissions;
 use crate::query::read::query_read;
-use crate::state::{ExecuteContext, QueryContext};
-use cosmwasm_std::{entry_point, to_json_binary, Env};
+use crate::query::roles::query_roles;
+use crate::state::{migrate_index_encoding, ExecuteContext, QueryContext};
+use cosmwasm_std::{attr, entry_point, to_json_binary, Env};
 use cosmwasm_std::{Binary, Deps, DepsMut, MessageInfo, Response};
 use cw2::set_contract_version;
 
@@ -37,6 +58,21 @@ pub fn execute(
         ExecuteMsg::Create(args) => exec_create(ctx, args),
         ExecuteMsg::Update(args) => exec_update(ctx, args),
         ExecuteMsg::Delete(args) => exec_delete(ctx, args),
+        ExecuteMsg::Batch(ops) => exec_batch(ctx, ops),
+        ExecuteMsg::AlterSchema(args) => exec_alter_schema(ctx, args),
+        ExecuteMsg::ContinueJob { limit } => exec_continue_job(ctx, limit),
+        ExecuteMsg::RebuildIndex { property } => exec_rebuild_index(ctx, property),
+        ExecuteMsg::Revert { id, revision } => exec_revert(ctx, id, revision),
+        ExecuteMsg::Restore { id } => exec_restore(ctx, id),
+        ExecuteMsg::Purge { limit } => exec_purge(ctx, limit),
+        ExecuteMsg::Prune { limit } => exec_prune(ctx, limit),
+        ExecuteMsg::TransferOwnership(args) => exec_transfer_ownership(ctx, args),
+        ExecuteMsg::SetMetadata(metadata) => exec_set_metadata(ctx, metadata),
+        ExecuteMsg::ProposeOperator(args) => exec_propose_operator(ctx, args),
+        ExecuteMsg::AcceptOperator {} => exec_accept_operator(ctx),
+        ExecuteMsg::CancelOperatorProposal {} => exec_cancel_operator_proposal(ctx),
+        ExecuteMsg::GrantRoles(args) => exec_grant_roles(ctx, args),
+        ExecuteMsg::RevokeRoles(args) => exec_revoke_roles(ctx, args),
     }
 }
 
@@ -48,8 +84,14 @@ pub fn query(
 ) -> Result<Binary, ContractError> {
     let ctx = QueryContext { deps, env };
     let result = match msg {
+        QueryMsg::Aggregate(args) => to_json_binary(&query_aggregate(ctx, args)?),
+        QueryMsg::History { id, cursor, limit } => to_json_binary(&query_history(ctx, id, cursor, limit)?),
         QueryMsg::Info {} => to_json_binary(&query_info(ctx)?),
         QueryMsg::Read(args) => to_json_binary(&query_read(ctx, args)?),
+        QueryMsg::Job {} => to_json_binary(&query_job(ctx)?),
+        QueryMsg::PendingOperator {} => to_json_binary(&query_pending_operator(ctx)?),
+        QueryMsg::Roles(args) => to_json_binary(&query_roles(ctx, args)?),
+        QueryMsg::Permissions { address } => to_json_binary(&query_permissions(ctx, address)?),
     }?;
     Ok(result)
 }
@@ -61,5 +103,9 @@ pub fn migrate(
     _msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    let job_queued = migrate_index_encoding(deps.storage)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("job_queued", job_queued.to_string()),
+    ]))
 }
@@ -9,12 +9,24 @@ pub enum ContractError {
     #[error("NotAuthorized: {reason:?}")]
     NotAuthorized { reason: String },
 
+    #[error("AlreadyExists: {reason:?}")]
+    AlreadyExists { reason: String },
+
     #[error("NotFound: {reason:?}")]
     NotFound { reason: String },
 
     #[error("ValidationError: {reason:?}")]
     ValidationError { reason: String },
 
+    #[error("RevisionMismatch: {reason:?}")]
+    RevisionMismatch { reason: String },
+
+    #[error("Referenced: {reason:?}")]
+    Referenced { reason: String },
+
+    #[error("BatchOperationFailed: operation {index}: {reason}")]
+    BatchOperationFailed { index: usize, reason: String },
+
     #[error("Unexpected: {reason:?}")]
     Unexpected { reason: String },
 }
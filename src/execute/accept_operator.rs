@@ -0,0 +1,10 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_accept_operator(mut ctx: ExecuteContext) -> Result<Response, ContractError> {
+    ctx.accept_operator()?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "accept_operator"),
+        attr("operator", ctx.info.sender.to_string()),
+    ]))
+}
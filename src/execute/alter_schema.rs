@@ -0,0 +1,17 @@
+use crate::{error::ContractError, msg::AlterSchemaArgs, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_alter_schema(
+    mut ctx: ExecuteContext,
+    args: AlterSchemaArgs,
+) -> Result<Response, ContractError> {
+    let AlterSchemaArgs { operations } = args;
+
+    ctx.require_operator()?;
+    let job_queued = ctx.alter_schema(operations)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "alter_schema"),
+        attr("job_queued", job_queued.to_string()),
+    ]))
+}
@@ -0,0 +1,48 @@
+use crate::{
+    error::ContractError,
+    execute::{create::exec_create, delete::exec_delete, update::exec_update},
+    msg::BatchOperation,
+    responses::{BatchOperationResult, BatchResponse, CreateResponse},
+    state::ExecuteContext,
+};
+use cosmwasm_std::{attr, from_json, to_json_binary, Response};
+
+/// Apply each operation through its single-operation handler. Any failure
+/// reverts the whole batch.
+pub fn exec_batch(
+    mut ctx: ExecuteContext,
+    ops: Vec<BatchOperation>,
+) -> Result<Response, ContractError> {
+    let n_ops = ops.len();
+    let mut results: Vec<BatchOperationResult> = Vec::with_capacity(n_ops);
+
+    for (i, op) in ops.into_iter().enumerate() {
+        let op_ctx = ExecuteContext::new(ctx.deps.branch(), ctx.env.to_owned(), ctx.info.to_owned());
+        let result = match op {
+            BatchOperation::Create(args) => exec_create(op_ctx, args).and_then(|resp| {
+                let data = resp.data.ok_or_else(|| ContractError::Unexpected {
+                    reason: "create returned no data".to_owned(),
+                })?;
+                let CreateResponse { id } = from_json(&data)?;
+                Ok(BatchOperationResult::Create { id })
+            }),
+            BatchOperation::Update(args) => {
+                let id = args.id;
+                exec_update(op_ctx, args).map(|_| BatchOperationResult::Update { id })
+            },
+            BatchOperation::Delete(args) => {
+                let id = args.id;
+                exec_delete(op_ctx, args).map(|_| BatchOperationResult::Delete { id })
+            },
+        }
+        .map_err(|e| ContractError::BatchOperationFailed {
+            index: i,
+            reason: e.to_string(),
+        })?;
+        results.push(result);
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "batch"), attr("size", n_ops.to_string())])
+        .set_data(to_json_binary(&BatchResponse { results })?))
+}
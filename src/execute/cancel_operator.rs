@@ -0,0 +1,7 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_cancel_operator_proposal(mut ctx: ExecuteContext) -> Result<Response, ContractError> {
+    ctx.cancel_operator_proposal()?;
+    Ok(Response::new().add_attributes(vec![attr("action", "cancel_operator_proposal")]))
+}
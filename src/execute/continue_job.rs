@@ -0,0 +1,21 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_continue_job(
+    mut ctx: ExecuteContext,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let (n_processed, skipped, done) = ctx.continue_job(limit)?;
+
+    let mut response = Response::new().add_attributes(vec![
+        attr("action", "continue_job"),
+        attr("processed", n_processed.to_string()),
+        attr("done", done.to_string()),
+    ]);
+    // Entities left out of an index, for the operator to fix up
+    if !skipped.is_empty() {
+        let skipped = skipped.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        response = response.add_attribute("skipped", skipped.join(","));
+    }
+    Ok(response)
+}
@@ -1,15 +1,29 @@
-use crate::{error::ContractError, msg::CreateArgs, state::ExecuteContext};
-use cosmwasm_std::{attr, Response};
+use crate::{error::ContractError, msg::CreateArgs, responses::CreateResponse, state::ExecuteContext};
+use cosmwasm_std::{attr, to_json_binary, Response, Uint64};
 
 pub fn exec_create(
     mut ctx: ExecuteContext,
     args: CreateArgs,
 ) -> Result<Response, ContractError> {
     let ExecuteContext { .. } = ctx;
-    let id = args.id.clone();
+    let CreateArgs {
+        id,
+        data,
+        owner,
+        validity,
+    } = args;
 
-    ctx.require_operator()?;
-    ctx.create_entity(args)?;
+    let owner = ctx.authorize_create(owner)?;
+    let id: Uint64 = ctx
+        .create_entity(CreateArgs {
+            id,
+            data,
+            owner,
+            validity,
+        })?
+        .into();
 
-    Ok(Response::new().add_attributes(vec![attr("action", "create"), attr("id", id)]))
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "create"), attr("id", id)])
+        .set_data(to_json_binary(&CreateResponse { id })?))
 }
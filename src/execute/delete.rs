@@ -1,4 +1,8 @@
-use crate::{error::ContractError, msg::DeleteArgs, state::ExecuteContext};
+use crate::{
+    error::ContractError,
+    msg::DeleteArgs,
+    state::{ExecuteContext, Role},
+};
 use cosmwasm_std::{attr, Response};
 
 pub fn exec_delete(
@@ -6,9 +10,9 @@ pub fn exec_delete(
     args: DeleteArgs,
 ) -> Result<Response, ContractError> {
     let ExecuteContext { .. } = ctx;
-    let DeleteArgs { id } = args;
+    let DeleteArgs { id, .. } = args;
 
-    ctx.require_operator()?;
+    ctx.require_owner_or_role(id.u64(), Role::Deleter)?;
     ctx.delete_entity(args)?;
 
     Ok(Response::new().add_attributes(vec![attr("action", "delete"), attr("id", id)]))
@@ -0,0 +1,19 @@
+use crate::{error::ContractError, msg::GrantRolesArgs, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_grant_roles(
+    mut ctx: ExecuteContext,
+    args: GrantRolesArgs,
+) -> Result<Response, ContractError> {
+    let GrantRolesArgs {
+        address,
+        roles,
+        expires,
+        quota,
+    } = args;
+    ctx.grant_roles(&address, roles, expires, quota)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "grant_roles"),
+        attr("address", address.to_string()),
+    ]))
+}
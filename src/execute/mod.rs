@@ -1,5 +1,18 @@
+pub mod accept_operator;
+pub mod alter_schema;
+pub mod batch;
+pub mod cancel_operator;
+pub mod continue_job;
 pub mod create;
 pub mod delete;
+pub mod grant_roles;
+pub mod propose_operator;
+pub mod prune;
+pub mod purge;
+pub mod rebuild_index;
+pub mod restore;
+pub mod revert;
+pub mod revoke_roles;
 pub mod set_metadata;
-pub mod set_operator;
+pub mod transfer_ownership;
 pub mod update;
@@ -0,0 +1,15 @@
+use crate::{error::ContractError, msg::ProposeOperatorArgs, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_propose_operator(
+    mut ctx: ExecuteContext,
+    args: ProposeOperatorArgs,
+) -> Result<Response, ContractError> {
+    let ProposeOperatorArgs { operator, expires } = args;
+    ctx.require_operator()?;
+    ctx.propose_operator(&operator, expires)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_operator"),
+        attr("operator", operator.to_string()),
+    ]))
+}
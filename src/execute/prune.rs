@@ -0,0 +1,15 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_prune(
+    mut ctx: ExecuteContext,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let (n_pruned, done) = ctx.prune_expired(limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "prune"),
+        attr("pruned", n_pruned.to_string()),
+        attr("done", done.to_string()),
+    ]))
+}
@@ -0,0 +1,16 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_purge(
+    mut ctx: ExecuteContext,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ctx.require_operator()?;
+    let (n_purged, done) = ctx.purge_trash(limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "purge"),
+        attr("purged", n_purged.to_string()),
+        attr("done", done.to_string()),
+    ]))
+}
@@ -0,0 +1,12 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_rebuild_index(
+    mut ctx: ExecuteContext,
+    property: String,
+) -> Result<Response, ContractError> {
+    ctx.require_operator()?;
+    ctx.rebuild_index(property.to_owned())?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "rebuild_index"), attr("property", property)]))
+}
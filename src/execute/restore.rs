@@ -0,0 +1,15 @@
+use crate::{
+    error::ContractError,
+    state::{ExecuteContext, Role},
+};
+use cosmwasm_std::{attr, Response, Uint64};
+
+pub fn exec_restore(
+    mut ctx: ExecuteContext,
+    id: Uint64,
+) -> Result<Response, ContractError> {
+    ctx.require_owner_or_role(id.u64(), Role::Deleter)?;
+    ctx.restore_entity(id)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "restore"), attr("id", id)]))
+}
@@ -0,0 +1,17 @@
+use crate::{error::ContractError, state::ExecuteContext};
+use cosmwasm_std::{attr, Response, Uint64};
+
+pub fn exec_revert(
+    mut ctx: ExecuteContext,
+    id: Uint64,
+    revision: u32,
+) -> Result<Response, ContractError> {
+    ctx.require_operator()?;
+    ctx.revert_entity(id, revision)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revert"),
+        attr("id", id),
+        attr("revision", revision.to_string()),
+    ]))
+}
@@ -0,0 +1,14 @@
+use crate::{error::ContractError, msg::RevokeRolesArgs, state::ExecuteContext};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_revoke_roles(
+    mut ctx: ExecuteContext,
+    args: RevokeRolesArgs,
+) -> Result<Response, ContractError> {
+    let RevokeRolesArgs { address, roles } = args;
+    ctx.revoke_roles(&address, roles)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revoke_roles"),
+        attr("address", address.to_string()),
+    ]))
+}
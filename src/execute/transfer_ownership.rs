@@ -0,0 +1,22 @@
+use crate::{
+    error::ContractError,
+    msg::TransferOwnershipArgs,
+    state::{ExecuteContext, Role},
+};
+use cosmwasm_std::{attr, Response};
+
+pub fn exec_transfer_ownership(
+    mut ctx: ExecuteContext,
+    args: TransferOwnershipArgs,
+) -> Result<Response, ContractError> {
+    let TransferOwnershipArgs { id, owner } = args.to_owned();
+
+    ctx.require_owner_or_role(id.u64(), Role::Updater)?;
+    ctx.transfer_ownership(args)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_ownership"),
+        attr("id", id),
+        attr("owner", owner.map(|o| o.to_string()).unwrap_or_default()),
+    ]))
+}
@@ -1,4 +1,8 @@
-use crate::{error::ContractError, msg::UpdateArgs, state::ExecuteContext};
+use crate::{
+    error::ContractError,
+    msg::UpdateArgs,
+    state::{ExecuteContext, Role},
+};
 use cosmwasm_std::{attr, Response};
 
 pub fn exec_update(
@@ -8,7 +12,7 @@ pub fn exec_update(
     let ExecuteContext { .. } = ctx;
     let UpdateArgs { id, .. } = args;
 
-    ctx.require_operator()?;
+    ctx.require_owner_or_role(id.u64(), Role::Updater)?;
     ctx.update_entity(args)?;
 
     Ok(Response::new().add_attributes(vec![attr("action", "update"), attr("id", id)]))
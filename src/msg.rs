@@ -1,17 +1,22 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Binary, Uint64};
+use cw_utils::Expiration;
 use serde_json;
 
 #[allow(unused_imports)]
-use crate::responses::{InfoResponse, ReadResponse};
-use crate::schema::EntitySchema;
-use crate::state::CollectionMetadata;
+use crate::responses::{
+    Accumulator, AggregateResponse, HistoryResponse, InfoResponse, JobResponse, PendingOperatorResponse,
+    PermissionsResponse, ReadResponse, RolesResponse,
+};
+use crate::schema::{EntityProperty, EntitySchema, IndexTransform};
+use crate::state::{CollectionConfig, CollectionMetadata, Role, Validity};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub schema: EntitySchema,
     pub operator: Option<Addr>,
     pub metadata: Option<CollectionMetadata>,
+    pub config: Option<CollectionConfig>,
 }
 
 #[cw_serde]
@@ -20,12 +25,89 @@ pub enum ExecuteMsg {
     Create(CreateArgs),
     Update(UpdateArgs),
     Delete(DeleteArgs),
+    Batch(Vec<BatchOperation>),
+    AlterSchema(AlterSchemaArgs),
+    ContinueJob { limit: Option<u32> },
+    RebuildIndex { property: String },
+    Revert { id: Uint64, revision: u32 },
+    Restore { id: Uint64 },
+    Purge { limit: Option<u32> },
+    Prune { limit: Option<u32> },
+    TransferOwnership(TransferOwnershipArgs),
+    SetMetadata(CollectionMetadata),
+    ProposeOperator(ProposeOperatorArgs),
+    AcceptOperator {},
+    CancelOperatorProposal {},
+    GrantRoles(GrantRolesArgs),
+    RevokeRoles(RevokeRolesArgs),
+}
+
+#[cw_serde]
+pub enum BatchOperation {
+    Create(CreateArgs),
+    Update(UpdateArgs),
+    Delete(DeleteArgs),
+}
+
+#[cw_serde]
+pub struct AlterSchemaArgs {
+    pub operations: Vec<SchemaOperation>,
+}
+
+#[cw_serde]
+pub enum SchemaOperation {
+    AddProperty(EntityProperty),
+    DropProperty {
+        name: String,
+    },
+    RenameProperty {
+        name: String,
+        new_name: String,
+    },
+    SetIndexed {
+        name: String,
+        indexed: bool,
+    },
+    SetSearchable {
+        name: String,
+        searchable: bool,
+    },
+    SetIndexTransforms {
+        name: String,
+        transforms: Option<Vec<IndexTransform>>,
+    },
+    SetRequired {
+        name: String,
+        required: bool,
+    },
+}
+
+#[cw_serde]
+pub struct ProposeOperatorArgs {
+    pub operator: Addr,
+    pub expires: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct GrantRolesArgs {
+    pub address: Addr,
+    pub roles: Vec<Role>,
+    pub expires: Option<Expiration>,
+    pub quota: Option<u32>,
+}
+
+#[cw_serde]
+pub struct RevokeRolesArgs {
+    pub address: Addr,
+    pub roles: Option<Vec<Role>>,
 }
 
 #[cw_serde]
 pub struct CreateArgs {
-    pub id: Uint64,
+    pub id: Option<Uint64>,
     pub data: serde_json::Value,
+    pub owner: Option<Addr>,
+    pub validity: Option<Validity>,
 }
 
 #[cw_serde]
@@ -50,6 +132,90 @@ pub enum ReadTarget {
         stop: Option<IndexBound>,
         limit: Option<u8>,
     },
+    /// Entities whose indexed string property starts with `prefix`
+    Prefix {
+        property: String,
+        prefix: String,
+        cursor: Option<Binary>,
+        limit: Option<u8>,
+    },
+    /// Entities whose searchable property contains any, or all, of the
+    /// words in `terms`, in ID order
+    Search {
+        property: String,
+        terms: Vec<String>,
+        match_all: Option<bool>,
+        cursor: Option<Binary>,
+        limit: Option<u8>,
+    },
+    Compound {
+        index: String,
+        prefix: Vec<serde_json::Value>,
+        cursor: Option<Binary>,
+        start: Option<IndexBound>,
+        stop: Option<IndexBound>,
+        limit: Option<u8>,
+    },
+    Owner {
+        owner: Addr,
+        cursor: Option<Binary>,
+        limit: Option<u8>,
+    },
+    Unique {
+        property: String,
+        value: serde_json::Value,
+    },
+    /// Entities whose reference property points at entity `id`
+    Referencing {
+        property: String,
+        id: Uint64,
+        cursor: Option<Binary>,
+        limit: Option<u8>,
+    },
+}
+
+/// A predicate over an entity's properties. Comparisons against a missing
+/// property are false, so `Not` of a comparison matches entities lacking it.
+#[cw_serde]
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Eq {
+        property: String,
+        value: serde_json::Value,
+    },
+    Ne {
+        property: String,
+        value: serde_json::Value,
+    },
+    Lt {
+        property: String,
+        value: serde_json::Value,
+    },
+    Lte {
+        property: String,
+        value: serde_json::Value,
+    },
+    Gt {
+        property: String,
+        value: serde_json::Value,
+    },
+    Gte {
+        property: String,
+        value: serde_json::Value,
+    },
+    In {
+        property: String,
+        values: Vec<serde_json::Value>,
+    },
+    Exists {
+        property: String,
+    },
+    Contains {
+        property: String,
+        value: serde_json::Value,
+    },
 }
 
 #[cw_serde]
@@ -57,6 +223,35 @@ pub struct ReadArgs {
     pub target: ReadTarget,
     pub desc: Option<bool>,
     pub select: Option<Vec<String>>,
+    pub filter: Option<Filter>,
+    pub timestamps: Option<bool>,
+    /// Include entities outside their validity window
+    pub include_inactive: Option<bool>,
+    /// Reference properties whose entities to inline into each entity read
+    pub expand: Option<Vec<Expand>>,
+}
+
+#[cw_serde]
+pub struct Expand {
+    /// Reference property, or dotted path to one
+    pub property: String,
+    /// Properties of the referenced entity to include, all by default
+    pub select: Option<Vec<String>>,
+    /// Reference properties of the referenced entity to expand in turn
+    pub expand: Option<Vec<Expand>>,
+}
+
+/// Aggregate a numeric property, or just count entities if none is given,
+/// over the entities matched by a target and filter. To continue a paged
+/// aggregate, pass back the cursor in the target and the accumulator.
+/// Timestamps are aggregated in nanoseconds, and decimals in units of their
+/// last fractional digit.
+#[cw_serde]
+pub struct AggregateArgs {
+    pub target: ReadTarget,
+    pub filter: Option<Filter>,
+    pub property: Option<String>,
+    pub accumulator: Option<Accumulator>,
 }
 
 #[cw_serde]
@@ -70,21 +265,62 @@ pub struct UpdateArgs {
     pub id: Uint64,
     pub data: serde_json::Value,
     pub mode: UpdateMode,
+    /// Fail unless the entity is at this revision
+    pub expected_revision: Option<u32>,
+    /// Replace the entity's validity window
+    pub validity: Option<Validity>,
 }
 
 #[cw_serde]
 pub struct DeleteArgs {
     pub id: Uint64,
+    /// Fail unless the entity is at this revision
+    pub expected_revision: Option<u32>,
+}
+
+#[cw_serde]
+pub struct TransferOwnershipArgs {
+    pub id: Uint64,
+    pub owner: Option<Addr>,
 }
 
 #[cw_serde]
 #[derive(cw_orch::QueryFns, QueryResponses)]
+#[allow(clippy::large_enum_variant)]
 pub enum QueryMsg {
     #[returns(Option<ReadResponse>)]
     Read(ReadArgs),
 
+    #[returns(AggregateResponse)]
+    Aggregate(AggregateArgs),
+
     #[returns(Option<InfoResponse>)]
     Info {},
+
+    #[returns(HistoryResponse)]
+    History {
+        id: Uint64,
+        cursor: Option<u32>,
+        limit: Option<u8>,
+    },
+
+    #[returns(Option<JobResponse>)]
+    Job {},
+
+    #[returns(Option<PendingOperatorResponse>)]
+    PendingOperator {},
+
+    #[returns(RolesResponse)]
+    Roles(RolesArgs),
+
+    #[returns(PermissionsResponse)]
+    Permissions { address: Addr },
+}
+
+#[cw_serde]
+pub struct RolesArgs {
+    pub cursor: Option<Addr>,
+    pub limit: Option<u8>,
 }
 
 #[cw_serde]
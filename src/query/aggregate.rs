@@ -0,0 +1,74 @@
+use cosmwasm_std::{SignedDecimal256, StdError, Uint64};
+
+use crate::{
+    error::ContractError,
+    msg::AggregateArgs,
+    query::read::{find, MAX_SCAN_SIZE},
+    responses::AggregateResponse,
+    schema::lookup_value,
+    state::{QueryContext, ENTITY, SCHEMA},
+};
+
+pub fn query_aggregate(
+    ctx: QueryContext,
+    args: AggregateArgs,
+) -> Result<AggregateResponse, ContractError> {
+    let QueryContext { deps, env } = ctx;
+    let AggregateArgs {
+        target,
+        filter,
+        property,
+        accumulator,
+    } = args;
+
+    let schema = SCHEMA.load(deps.storage)?;
+    let prop = property
+        .map(|name| {
+            let prop = schema.property(&name).ok_or_else(|| ContractError::ValidationError {
+                reason: format!("property {} not found", name),
+            })?;
+            if !prop.is_numeric() {
+                return Err(ContractError::ValidationError {
+                    reason: format!("property {} not numeric", name),
+                });
+            }
+            Ok::<_, ContractError>((name, prop))
+        })
+        .transpose()?;
+
+    let (ids, cursor) = find(
+        deps.storage,
+        target,
+        false,
+        filter.as_ref(),
+        Some(env.block.time),
+        MAX_SCAN_SIZE,
+        MAX_SCAN_SIZE,
+    )?;
+
+    let mut acc = accumulator.unwrap_or_default();
+    for id in ids {
+        if let Some((name, prop)) = &prop {
+            let entity = ENTITY.load(deps.storage, id.u64())?;
+            let value = match lookup_value(&entity, name) {
+                Some(v) if !v.is_null() => prop.to_int256(v)?,
+                _ => continue,
+            };
+            acc.sum = acc.sum.checked_add(value).map_err(StdError::from)?;
+            acc.min = Some(acc.min.map_or(value, |x| x.min(value)));
+            acc.max = Some(acc.max.map_or(value, |x| x.max(value)));
+        }
+        acc.count += Uint64::one();
+    }
+
+    let average = match prop {
+        Some(_) if !acc.count.is_zero() => SignedDecimal256::checked_from_ratio(acc.sum, acc.count).ok(),
+        _ => None,
+    };
+
+    Ok(AggregateResponse {
+        accumulator: acc,
+        average,
+        cursor,
+    })
+}
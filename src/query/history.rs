@@ -0,0 +1,91 @@
+use cosmwasm_std::{Order, Uint64};
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    query::read::MAX_PAGE_SIZE,
+    responses::{HistoryResponse, PropertyChange, RevisionEntry},
+    state::{load_revision, load_timestamp, EntityRevision, QueryContext, ENTITY, HISTORY, UPDATED_AT_PROPERTY},
+};
+
+pub fn query_history(
+    ctx: QueryContext,
+    id: Uint64,
+    cursor: Option<u32>,
+    limit: Option<u8>,
+) -> Result<HistoryResponse, ContractError> {
+    let QueryContext { deps, .. } = ctx;
+    let limit = limit.unwrap_or(10).min(MAX_PAGE_SIZE) as usize;
+    let data = ENTITY
+        .may_load(deps.storage, id.u64())?
+        .ok_or_else(|| ContractError::NotFound {
+            reason: format!("entity {} not found", id),
+        })?;
+    let revision = load_revision(deps.storage, id.u64())?;
+
+    // The current revision comes first, followed by retained ones, newest first
+    let mut versions: Vec<(u32, EntityRevision)> = vec![];
+    if cursor.is_none() {
+        if let Some(time) = load_timestamp(deps.storage, UPDATED_AT_PROPERTY, id.u64())? {
+            versions.push((revision, EntityRevision { data, time }));
+        }
+    }
+    for result in HISTORY
+        .prefix(id.u64())
+        .range(deps.storage, None, cursor.map(Bound::exclusive), Order::Descending)
+        .take(limit.saturating_sub(versions.len()))
+    {
+        versions.push(result?);
+    }
+
+    let mut revisions: Vec<RevisionEntry> = Vec::with_capacity(versions.len());
+    for (revision, EntityRevision { data, time }) in versions {
+        let changes = match revision.checked_sub(1) {
+            Some(prev) => HISTORY
+                .may_load(deps.storage, (id.u64(), prev))?
+                .map(|prev| diff(&prev.data, &data)),
+            None => None,
+        };
+        revisions.push(RevisionEntry {
+            revision,
+            time,
+            data,
+            changes,
+        });
+    }
+
+    let cursor = if revisions.len() == limit {
+        revisions.last().map(|r| r.revision)
+    } else {
+        None
+    };
+
+    Ok(HistoryResponse {
+        id,
+        revision,
+        revisions,
+        cursor,
+    })
+}
+
+/// Properties that differ between two versions of an entity, in name order.
+fn diff(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+) -> Vec<PropertyChange> {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| PropertyChange {
+            property: name.to_owned(),
+            old: old.get(name).cloned(),
+            new: new.get(name).cloned(),
+        })
+        .collect()
+}
@@ -1,13 +1,14 @@
 use crate::{
     error::ContractError,
     responses::InfoResponse,
-    state::{QueryContext, COUNT, METADATA, OPERATOR, SCHEMA},
+    state::{QueryContext, CONFIG, COUNT, METADATA, OPERATOR, SCHEMA},
 };
 
 pub fn query_info(ctx: QueryContext) -> Result<InfoResponse, ContractError> {
     let QueryContext { deps, .. } = ctx;
     Ok(InfoResponse {
         metadata: METADATA.load(deps.storage)?,
+        config: CONFIG.may_load(deps.storage)?.unwrap_or_default(),
         operator: OPERATOR.load(deps.storage)?,
         schema: SCHEMA.load(deps.storage)?,
         size: COUNT.load(deps.storage)?,
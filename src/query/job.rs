@@ -0,0 +1,13 @@
+use crate::{
+    error::ContractError,
+    responses::JobResponse,
+    state::{QueryContext, JOB},
+};
+
+pub fn query_job(ctx: QueryContext) -> Result<Option<JobResponse>, ContractError> {
+    let QueryContext { deps, .. } = ctx;
+    Ok(JOB.may_load(deps.storage)?.map(|job| JobResponse {
+        incomplete_indices: job.incomplete_indices(),
+        job,
+    }))
+}
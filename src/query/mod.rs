@@ -1,2 +1,8 @@
+pub mod aggregate;
+pub mod history;
 pub mod info;
+pub mod job;
+pub mod pending_operator;
+pub mod permissions;
 pub mod read;
+pub mod roles;
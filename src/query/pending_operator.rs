@@ -0,0 +1,15 @@
+use crate::{
+    error::ContractError,
+    responses::PendingOperatorResponse,
+    state::{QueryContext, PENDING_OPERATOR},
+};
+
+pub fn query_pending_operator(ctx: QueryContext) -> Result<Option<PendingOperatorResponse>, ContractError> {
+    let QueryContext { deps, env } = ctx;
+    Ok(PENDING_OPERATOR
+        .may_load(deps.storage)?
+        .map(|proposal| PendingOperatorResponse {
+            is_expired: proposal.expires.is_expired(&env.block),
+            proposal,
+        }))
+}
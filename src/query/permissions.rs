@@ -0,0 +1,30 @@
+use cosmwasm_std::Addr;
+
+use crate::{
+    error::ContractError,
+    responses::PermissionsResponse,
+    state::{QueryContext, Role, OPERATOR, ROLES},
+};
+
+pub fn query_permissions(
+    ctx: QueryContext,
+    address: Addr,
+) -> Result<PermissionsResponse, ContractError> {
+    let QueryContext { deps, env } = ctx;
+    let is_operator = address == OPERATOR.load(deps.storage)?;
+    let grant = ROLES.may_load(deps.storage, &address)?;
+    let allows = |role: Role| is_operator || grant.as_ref().map(|g| g.allows(&role, &env.block)).unwrap_or(false);
+    Ok(PermissionsResponse {
+        can_create: allows(Role::Writer),
+        can_update: allows(Role::Updater),
+        can_delete: allows(Role::Deleter),
+        can_manage_roles: is_operator
+            || grant
+                .as_ref()
+                .map(|g| !g.expires.is_expired(&env.block) && g.roles.contains(&Role::Admin))
+                .unwrap_or(false),
+        is_operator,
+        address,
+        grant,
+    })
+}
@@ -1,56 +1,334 @@
-use std::marker::PhantomData;
-use std::mem::size_of;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem::size_of,
+};
 
-use cosmwasm_std::{Binary, Order, Storage, Uint64};
+use cosmwasm_std::{Addr, Binary, Deps, Order, QuerierWrapper, StdResult, Storage, Timestamp, Uint64};
 use cw_storage_plus::Bound;
 
 use serde_json;
 
 use crate::{
     error::ContractError,
-    msg::{IndexBound, ReadArgs, ReadTarget},
-    responses::{Entity, ReadResponse},
-    schema::EntityProperty,
-    state::{PropertyIndex, QueryContext, ENTITY, SCHEMA},
+    msg::{Expand, Filter, IndexBound, QueryMsg, ReadArgs, ReadTarget},
+    responses::{Entity, EntityTimestamps, ReadResponse},
+    schema::{lookup, lookup_value, tokenize, EntityProperty, EntityPropertyParams, EntitySchema},
+    state::{
+        load_timestamp, timestamp_property, PropertyIndex, QueryContext, UniqueIndex, CREATED_AT_PROPERTY,
+        CREATED_HEIGHT, ENTITY, JOB, OWNED, OWNER, SCHEMA, UPDATED_AT_PROPERTY, UPDATED_HEIGHT, VALIDITY,
+    },
 };
 
 pub const MAX_PAGE_SIZE: u8 = 50;
+pub const MAX_SCAN_SIZE: usize = 500;
+pub const MAX_SEARCH_TERMS: usize = 8;
+pub const MAX_EXPAND_DEPTH: usize = 3;
+/// Most referenced entities inlined into one page of a read, after which the
+/// rest of the page is left unexpanded
+pub const MAX_EXPANDED: usize = 100;
 
 pub fn query_read(
     ctx: QueryContext,
-    mut args: ReadArgs,
+    args: ReadArgs,
+) -> Result<ReadResponse, ContractError> {
+    let QueryContext { deps, env } = ctx;
+    read(deps, args, env.block.time)
+}
+
+pub fn read(
+    deps: Deps,
+    args: ReadArgs,
+    time: Timestamp,
 ) -> Result<ReadResponse, ContractError> {
-    let QueryContext { deps, .. } = ctx;
-    if let ReadTarget::Equals {
+    let storage = deps.storage;
+    let ReadArgs {
+        target,
+        desc,
+        select,
+        filter,
+        timestamps,
+        include_inactive,
+        expand,
+    } = args;
+    let expand = expand.unwrap_or_default();
+    if expand_depth(&expand) > MAX_EXPAND_DEPTH {
+        return Err(ContractError::ValidationError {
+            reason: format!("expand deeper than {}", MAX_EXPAND_DEPTH),
+        });
+    }
+    let desc = desc.unwrap_or_default();
+    let active_at = if include_inactive.unwrap_or(false) {
+        None
+    } else {
+        Some(time)
+    };
+    let (ids, cursor) = find(
+        storage,
+        target,
+        desc,
+        filter.as_ref(),
+        active_at,
+        10,
+        MAX_PAGE_SIZE as usize,
+    )?;
+
+    // Now build vec of IDs and selected entity data, if any
+    let mut entities: Vec<Entity> = Vec::with_capacity(ids.len());
+
+    if let Some(selected_prop_names) = select {
+        for id in ids {
+            // Select all fields or only specific ones
+            let entity_value = ENTITY.load(storage, id.u64())?;
+            let owner = OWNER.may_load(storage, id.u64())?;
+            entities.push(Entity {
+                id,
+                owner,
+                data: Some(select_data(entity_value, &selected_prop_names)),
+                timestamps: None,
+                expanded: None,
+            })
+        }
+    } else {
+        for id in ids {
+            entities.push(Entity {
+                id,
+                owner: OWNER.may_load(storage, id.u64())?,
+                data: None,
+                timestamps: None,
+                expanded: None,
+            });
+        }
+    }
+
+    if timestamps.unwrap_or(false) {
+        for entity in entities.iter_mut() {
+            entity.timestamps = Some(load_entity_timestamps(storage, entity.id.u64())?);
+        }
+    }
+
+    if !expand.is_empty() {
+        let schema = SCHEMA.load(storage)?;
+        let mut budget = MAX_EXPANDED;
+        // Entities past the point the budget runs out are left unexpanded
+        for entity in entities.iter_mut() {
+            let data = ENTITY.load(storage, entity.id.u64())?;
+            entity.expanded = expand_refs(deps, &schema, &data, &expand, active_at, &mut budget)?;
+            if entity.expanded.is_none() {
+                break;
+            }
+        }
+    }
+
+    // Return results and the next cursor
+    Ok(ReadResponse { entities, cursor })
+}
+
+/// Project an entity's data onto the selected properties, or keep all of it
+/// if "*" is selected.
+fn select_data(
+    value: serde_json::Value,
+    select: &[String],
+) -> serde_json::Value {
+    if select.iter().any(|k| k == "*") {
+        return value;
+    }
+    let value_map = value.as_object().unwrap();
+    let mut filtered_data = serde_json::Map::new();
+    for k in select.iter() {
+        if let Some(v) = lookup(value_map, k) {
+            project(&mut filtered_data, k, v.to_owned());
+        }
+    }
+    serde_json::Value::Object(filtered_data)
+}
+
+fn expand_depth(expand: &[Expand]) -> usize {
+    expand
+        .iter()
+        .map(|x| 1 + expand_depth(x.expand.as_deref().unwrap_or_default()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Load the entities referenced by an entity through each expanded property,
+/// with their own expansions, counting each against `budget`. Missing and
+/// inactive referenced entities are left out. Returns None if the budget
+/// runs out first.
+fn expand_refs(
+    deps: Deps,
+    schema: &EntitySchema,
+    data: &serde_json::Value,
+    expand: &[Expand],
+    active_at: Option<Timestamp>,
+    budget: &mut usize,
+) -> Result<Option<BTreeMap<String, Entity>>, ContractError> {
+    let mut expanded = BTreeMap::new();
+    for Expand {
         property,
-        value,
-        limit,
-        cursor,
-    } = &args.target
+        select,
+        expand: nested,
+    } in expand
     {
-        let start = Some(IndexBound::Inclusive(value.to_owned()));
-        args.target = ReadTarget::Range {
-            property: property.to_owned(),
-            cursor: cursor.to_owned(),
-            limit: limit.to_owned(),
-            stop: start.to_owned(),
-            start,
+        let prop = schema
+            .property(property)
+            .ok_or_else(|| ContractError::ValidationError {
+                reason: format!("property {} not found", property),
+            })?;
+        let EntityPropertyParams::Ref { contract, .. } = &prop.value else {
+            return Err(ContractError::ValidationError {
+                reason: format!("property {} not a reference", property),
+            });
         };
-        read(deps.storage, args)
-    } else {
-        read(deps.storage, args)
+        let Some(value) = lookup_value(data, property) else {
+            continue;
+        };
+        let id = prop.ref_id(value)?;
+        if *budget == 0 {
+            return Ok(None);
+        }
+        *budget -= 1;
+
+        let select = select.to_owned().unwrap_or_else(|| vec!["*".to_owned()]);
+        let entity = match contract {
+            None => {
+                let Some(value) = ENTITY.may_load(deps.storage, id)? else {
+                    continue;
+                };
+                if let Some(time) = active_at {
+                    if !VALIDITY.may_load(deps.storage, id)?.unwrap_or_default().contains(time) {
+                        continue;
+                    }
+                }
+                let nested = nested.as_deref().unwrap_or_default();
+                let nested_expanded = match nested.is_empty() {
+                    true => None,
+                    false => match expand_refs(deps, schema, &value, nested, active_at, budget)? {
+                        Some(nested_expanded) => Some(nested_expanded),
+                        None => return Ok(None),
+                    },
+                };
+                Entity {
+                    id: id.into(),
+                    owner: OWNER.may_load(deps.storage, id)?,
+                    data: Some(select_data(value.to_owned(), &select)),
+                    timestamps: None,
+                    expanded: nested_expanded,
+                }
+            },
+            Some(contract) => {
+                match read_remote(
+                    deps.querier,
+                    contract,
+                    id,
+                    Some(select),
+                    nested.to_owned(),
+                    active_at.is_none(),
+                )? {
+                    Some(entity) => entity,
+                    None => continue,
+                }
+            },
+        };
+        expanded.insert(property.to_owned(), entity);
     }
+    Ok(Some(expanded))
 }
 
-pub fn read(
+/// Read an entity from another cw-entity contract, or None if it doesn't
+/// exist there.
+pub fn read_remote(
+    querier: QuerierWrapper,
+    contract: &Addr,
+    id: u64,
+    select: Option<Vec<String>>,
+    expand: Option<Vec<Expand>>,
+    include_inactive: bool,
+) -> Result<Option<Entity>, ContractError> {
+    let args = ReadArgs {
+        target: ReadTarget::Ids(vec![id.into()]),
+        desc: None,
+        select,
+        filter: None,
+        timestamps: None,
+        include_inactive: Some(include_inactive),
+        expand,
+    };
+    let response: ReadResponse = querier.query_wasm_smart(contract, &QueryMsg::Read(args))?;
+    Ok(response.entities.into_iter().next())
+}
+
+/// Copy a selected value into the projection. A nested field selected by its
+/// dotted path is placed under objects along that path.
+fn project(
+    out: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    value: serde_json::Value,
+) {
+    let mut names: Vec<&str> = path.split('.').collect();
+    let last = names.pop().unwrap();
+    let mut obj = out;
+    for name in names {
+        let entry = obj
+            .entry(name.to_owned())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        obj = match entry.as_object_mut() {
+            Some(obj) => obj,
+            None => return,
+        };
+    }
+    obj.insert(last.to_owned(), value);
+}
+
+fn load_entity_timestamps(
     storage: &dyn Storage,
-    args: ReadArgs,
-) -> Result<ReadResponse, ContractError> {
-    let ReadArgs { target, desc, select } = args;
-    let desc = desc.unwrap_or_default();
+    id: u64,
+) -> Result<EntityTimestamps, ContractError> {
+    let not_found = || ContractError::NotFound {
+        reason: format!("entity {} not found", id),
+    };
+    Ok(EntityTimestamps {
+        created_at: load_timestamp(storage, CREATED_AT_PROPERTY, id)?.ok_or_else(not_found)?,
+        created_height: CREATED_HEIGHT.may_load(storage, id)?,
+        updated_at: load_timestamp(storage, UPDATED_AT_PROPERTY, id)?.ok_or_else(not_found)?,
+        updated_height: UPDATED_HEIGHT.may_load(storage, id)?,
+        validity: VALIDITY.may_load(storage, id)?,
+    })
+}
+
+/// Resolve a read target to the IDs of entities passing the filter, and
+/// active at `active_at` if given, in index order, along with the cursor to
+/// resume from. A target without a limit returns up to `default_limit` IDs,
+/// and never more than `max_limit`.
+pub fn find(
+    storage: &dyn Storage,
+    target: ReadTarget,
+    desc: bool,
+    filter: Option<&Filter>,
+    active_at: Option<Timestamp>,
+    default_limit: usize,
+    max_limit: usize,
+) -> Result<(Vec<Uint64>, Option<Binary>), ContractError> {
     let order = if desc { Order::Descending } else { Order::Ascending };
+    let to_limit = |limit: Option<u8>| limit.map(usize::from).unwrap_or(default_limit).min(max_limit);
+
+    // Candidates from the target are checked against the filter, if any
+    let filter_schema = filter.map(|_| SCHEMA.load(storage)).transpose()?;
+    let accept = |id: u64| -> Result<bool, ContractError> {
+        if let Some(time) = active_at {
+            if !VALIDITY.may_load(storage, id)?.unwrap_or_default().contains(time) {
+                return Ok(false);
+            }
+        }
+        match (filter, &filter_schema) {
+            (Some(filter), Some(schema)) => match ENTITY.may_load(storage, id)? {
+                Some(entity) => schema.matches(filter, &entity),
+                None => Ok(false),
+            },
+            _ => Ok(true),
+        }
+    };
 
-    let mut ids: Vec<Uint64> = Vec::with_capacity(MAX_PAGE_SIZE as usize);
+    let mut ids: Vec<Uint64> = Vec::with_capacity(max_limit);
     let mut next_cursor_info: Option<(Vec<u8>, u64)> = None;
 
     match target {
@@ -58,7 +336,28 @@ pub fn read(
             if desc {
                 target_ids.reverse();
             }
-            ids = target_ids
+            // Skip IDs of entities that don't exist, or are in the trash
+            for id in target_ids {
+                if ENTITY.has(storage, id.u64()) && accept(id.u64())? {
+                    ids.push(id);
+                }
+            }
+        },
+        ReadTarget::Equals {
+            property,
+            value,
+            cursor,
+            limit,
+        } => {
+            let start = Some(IndexBound::Inclusive(value));
+            let target = ReadTarget::Range {
+                property,
+                cursor,
+                limit,
+                stop: start.to_owned(),
+                start,
+            };
+            return find(storage, target, desc, filter, active_at, default_limit, max_limit);
         },
         ReadTarget::Range {
             property: prop_name,
@@ -67,118 +366,555 @@ pub fn read(
             start,
             stop,
         } => {
-            let limit = limit.unwrap_or(10).min(MAX_PAGE_SIZE) as usize;
+            let limit = to_limit(limit);
 
             let index_name = format!("_ix_{}", prop_name);
-            let index = PropertyIndex::new(index_name.as_str());
+            let prop = load_indexed_property(storage, &prop_name)?;
+
+            // "start" is where iteration begins, so in descending order it's
+            // the upper bound. A cursor replaces start exclusively.
+            let start = match cursor {
+                Some(cursor) => Some(ScanBound::Cursor(decode_cursor(&prop, &cursor)?)),
+                None => start
+                    .map(|b| ScanBound::from_index_bound(&[], &prop, transform_bound(&prop, b), 0))
+                    .transpose()?,
+            };
+            let stop = stop
+                .map(|b| ScanBound::from_index_bound(&[], &prop, transform_bound(&prop, b), 0))
+                .transpose()?;
+
+            next_cursor_info = scan_index(storage, &index_name, start, stop, desc, limit, &accept, &mut ids)?;
+        },
+        ReadTarget::Prefix {
+            property: prop_name,
+            prefix,
+            cursor,
+            limit,
+        } => {
+            let limit = to_limit(limit);
+
+            let index_name = format!("_ix_{}", prop_name);
+            let prop = load_indexed_property(storage, &prop_name)?;
+            if !matches!(prop.value, EntityPropertyParams::String { .. }) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("property {} not a string", prop_name),
+                });
+            }
+            let prefix = prop.transform(&prefix.into(), true);
+            let key = prefix.as_str().unwrap_or_default().as_bytes().to_vec();
+            if key.len() > prop.key_size() {
+                return Err(ContractError::ValidationError {
+                    reason: format!("prefix longer than property {}", prop_name),
+                });
+            }
+
+            // Keys starting with the prefix lie between the prefix padded
+            // with the smallest and largest bytes.
+            let suffix_size = prop.key_size() - key.len();
+            let start = match cursor {
+                Some(cursor) => ScanBound::Cursor(decode_cursor(&prop, &cursor)?),
+                None => ScanBound::new(key.to_owned(), suffix_size, true),
+            };
+            let stop = ScanBound::new(key, suffix_size, true);
+
+            next_cursor_info = scan_index(
+                storage,
+                &index_name,
+                Some(start),
+                Some(stop),
+                desc,
+                limit,
+                &accept,
+                &mut ids,
+            )?;
+        },
+        ReadTarget::Compound {
+            index: index_name,
+            prefix,
+            cursor,
+            limit,
+            start,
+            stop,
+        } => {
+            let limit = to_limit(limit);
 
             let schema = SCHEMA.load(storage)?;
-            let prop = schema
+            let index = schema
+                .compound_index(&index_name)
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("index {} not found", index_name),
+                })?;
+            let props = index
                 .properties
                 .iter()
-                .find(|p| p.name == prop_name)
-                .ok_or_else(|| ContractError::ValidationError { reason: format!("") })?;
-
-            let mut tmp_cursor: Box<Vec<u8>> = Box::new(vec![]);
-            let mut tmp_start: Box<Vec<u8>> = Box::new(vec![]);
-            let mut tmp_stop: Box<Vec<u8>> = Box::new(vec![]);
-
-            let mut min = cursor
-                .and_then(|cursor_bytes| {
-                    let id_size = size_of::<u64>();
-                    let id_bytes = cursor_bytes[cursor_bytes.len() - id_size..].try_into().unwrap();
-                    let key = prop.pad(cursor_bytes[..cursor_bytes.len() - id_size].to_vec()).unwrap();
-                    let id = u64::from_le_bytes(id_bytes);
-                    *tmp_cursor = key;
-                    Some(Bound::Exclusive((((*tmp_cursor).as_slice(), id), PhantomData)))
-                })
-                .or_else(|| {
-                    start.and_then(|b| {
-                        Some(match b {
-                            IndexBound::Inclusive(v) => {
-                                *tmp_start = prop.to_bytes(&v).unwrap();
-                                Bound::Inclusive((((*tmp_start).as_slice(), u64::MIN), PhantomData))
-                            },
-                            IndexBound::Exclusive(v) => {
-                                *tmp_start = prop.to_bytes(&v).unwrap();
-                                Bound::Exclusive((((*tmp_start).as_slice(), u64::MIN), PhantomData))
-                            },
-                        })
-                    })
-                });
-
-            let mut max = stop.and_then(|b| {
-                Some(match b {
-                    IndexBound::Inclusive(v) => {
-                        *tmp_stop = prop.to_bytes(&v).unwrap();
-                        Bound::Inclusive((((*tmp_stop).as_slice(), u64::MAX), PhantomData))
-                    },
-                    IndexBound::Exclusive(v) => {
-                        *tmp_stop = prop.to_bytes(&v).unwrap();
-                        Bound::Exclusive((((*tmp_stop).as_slice(), u64::MAX), PhantomData))
-                    },
-                })
-            });
+                .map(|name| schema.property(name).unwrap())
+                .collect::<Vec<_>>();
 
-            if desc {
-                let tmp = min;
-                min = max;
-                max = tmp;
+            if prefix.len() > props.len() || (prefix.len() == props.len() && (start.is_some() || stop.is_some())) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("too many values for index {}", index_name),
+                });
             }
 
-            for result in index.keys(storage, min, max, order).take(limit) {
-                let (bytes, id) = result?;
-                next_cursor_info = Some((bytes, id));
-                ids.push(id.into());
+            // Encode the equality prefix, then bound the next property by
+            // start and stop, or by the prefix alone if neither is given.
+            let mut prefix_key: Vec<u8> = vec![];
+            for (prop, value) in props.iter().zip(prefix.iter()) {
+                prefix_key.extend(prop.to_bytes(value)?);
             }
+            let key_size: usize = props.iter().map(|p| p.key_size()).sum();
+            let range_prop = props.get(prefix.len());
+            let suffix_size = key_size - prefix_key.len() - range_prop.map(|p| p.key_size()).unwrap_or(0);
+            let to_bound = |b: Option<IndexBound>| -> Result<ScanBound, ContractError> {
+                match (range_prop, b) {
+                    (Some(prop), Some(b)) => ScanBound::from_index_bound(&prefix_key, prop, b, suffix_size),
+                    _ => Ok(ScanBound::new(prefix_key.to_owned(), key_size - prefix_key.len(), true)),
+                }
+            };
+
+            let start = match cursor {
+                Some(cursor) => ScanBound::Cursor(split_cursor(&cursor, key_size)?),
+                None => to_bound(start)?,
+            };
+            let stop = to_bound(stop)?;
+
+            let index_name = format!("_cx_{}", index_name);
+            next_cursor_info = scan_index(
+                storage,
+                &index_name,
+                Some(start),
+                Some(stop),
+                desc,
+                limit,
+                &accept,
+                &mut ids,
+            )?;
         },
-        _ => {
-            return Err(ContractError::Unexpected {
-                reason: "Eq not supported by range".to_owned(),
-            })
+        ReadTarget::Owner { owner, cursor, limit } => {
+            let limit = to_limit(limit);
+            let cursor_id = cursor.map(|c| decode_id_cursor(&c)).transpose()?;
+            let bound = cursor_id.map(Bound::exclusive);
+            let (min, max) = if desc { (None, bound) } else { (bound, None) };
+            for result in OWNED.prefix(&owner).keys(storage, min, max, order).take(MAX_SCAN_SIZE) {
+                let id = result?;
+                next_cursor_info = Some((vec![], id));
+                if accept(id)? {
+                    ids.push(id.into());
+                    if ids.len() == limit {
+                        break;
+                    }
+                }
+            }
         },
-    };
+        ReadTarget::Search {
+            property: prop_name,
+            terms,
+            match_all,
+            cursor,
+            limit,
+        } => {
+            let limit = to_limit(limit);
 
-    // Now build vec of IDs and selected entity data, if any
-    let mut entities: Vec<Entity> = Vec::with_capacity(ids.len());
+            let schema = SCHEMA.load(storage)?;
+            let prop = schema
+                .property(&prop_name)
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("property {} not found", prop_name),
+                })?;
+            if !prop.searchable.unwrap_or(false) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("property {} not searchable", prop_name),
+                });
+            }
+            require_index_complete(storage, &format!("_tx_{}", prop_name))?;
+            let tokens: BTreeSet<String> = terms.iter().flat_map(|term| tokenize(term)).collect();
+            if tokens.is_empty() || tokens.len() > MAX_SEARCH_TERMS {
+                return Err(ContractError::ValidationError {
+                    reason: format!("expected 1 to {} search terms", MAX_SEARCH_TERMS),
+                });
+            }
 
-    if let Some(selected_prop_names) = select {
-        let select_star = selected_prop_names.iter().find(|k| *k == "*").is_some();
-        for id in ids {
-            // Select all fields or only specific ones
-            let entity_value = ENTITY.load(storage, id.u64())?;
-            if select_star {
-                entities.push(Entity {
-                    id,
-                    data: Some(entity_value),
-                })
+            let index_name = format!("_tx_{}", prop_name);
+            let index = PropertyIndex::new(&index_name);
+            let cursor_id = cursor.map(|c| decode_id_cursor(&c)).transpose()?;
+            let bounds = || {
+                let bound = cursor_id.map(Bound::exclusive);
+                if desc {
+                    (None, bound)
+                } else {
+                    (bound, None)
+                }
+            };
+
+            if match_all.unwrap_or(false) {
+                // Walk the entities with the first word, keeping those that
+                // have every other word too
+                let mut words = tokens.iter();
+                let first = words.next().unwrap();
+                let rest: Vec<&String> = words.collect();
+                let (min, max) = bounds();
+                for result in index
+                    .prefix(first.as_bytes())
+                    .keys(storage, min, max, order)
+                    .take(MAX_SCAN_SIZE)
+                {
+                    let id = result?;
+                    next_cursor_info = Some((vec![], id));
+                    if rest.iter().all(|token| index.has(storage, (token.as_bytes(), id))) && accept(id)? {
+                        ids.push(id.into());
+                        if ids.len() == limit {
+                            break;
+                        }
+                    }
+                }
             } else {
-                let value_map = entity_value.as_object().unwrap();
-                let mut filtered_data = serde_json::Map::new();
-                for k in selected_prop_names.iter() {
-                    if let Some(v) = value_map.get(k) {
-                        filtered_data.insert(k.to_owned(), v.to_owned());
+                // Merge the next entities with each word in ID order. A word
+                // with more entities than the limit only vouches for IDs up
+                // to the last one read, so the merge stops there.
+                let mut candidates: BTreeSet<u64> = BTreeSet::new();
+                let mut cutoff: Option<u64> = None;
+                for token in tokens.iter() {
+                    let (min, max) = bounds();
+                    let found = index
+                        .prefix(token.as_bytes())
+                        .keys(storage, min, max, order)
+                        .take(limit)
+                        .collect::<StdResult<Vec<u64>>>()?;
+                    if let (true, Some(last)) = (found.len() == limit, found.last()) {
+                        cutoff = Some(match cutoff {
+                            Some(c) if desc => c.max(*last),
+                            Some(c) => c.min(*last),
+                            None => *last,
+                        });
                     }
+                    candidates.extend(found);
                 }
-                entities.push(Entity {
-                    id,
-                    data: Some(serde_json::Value::Object(filtered_data)),
-                })
+                let candidates: Vec<u64> = if desc {
+                    candidates.into_iter().rev().collect()
+                } else {
+                    candidates.into_iter().collect()
+                };
+                for id in candidates {
+                    if cutoff.is_some_and(|c| if desc { id < c } else { id > c }) {
+                        break;
+                    }
+                    next_cursor_info = Some((vec![], id));
+                    if accept(id)? {
+                        ids.push(id.into());
+                        if ids.len() == limit {
+                            break;
+                        }
+                    }
+                }
+            }
+        },
+        ReadTarget::Referencing {
+            property: prop_name,
+            id,
+            cursor,
+            limit,
+        } => {
+            let limit = to_limit(limit);
+
+            let schema = SCHEMA.load(storage)?;
+            let prop = schema
+                .property(&prop_name)
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("property {} not found", prop_name),
+                })?;
+            if !matches!(prop.value, EntityPropertyParams::Ref { .. }) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("property {} not a reference", prop_name),
+                });
+            }
+            let index_name = format!("_rx_{}", prop_name);
+            require_index_complete(storage, &index_name)?;
+            let key = id.u64().to_be_bytes().to_vec();
+            let start = match cursor {
+                Some(cursor) => ScanBound::Cursor(decode_cursor(prop, &cursor)?),
+                None => ScanBound::new(key.to_owned(), 0, true),
+            };
+            let stop = ScanBound::new(key, 0, true);
+
+            next_cursor_info = scan_index(
+                storage,
+                &index_name,
+                Some(start),
+                Some(stop),
+                desc,
+                limit,
+                &accept,
+                &mut ids,
+            )?;
+        },
+        ReadTarget::Unique {
+            property: prop_name,
+            value,
+        } => {
+            let schema = SCHEMA.load(storage)?;
+            let prop = schema
+                .property(&prop_name)
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("property {} not found", prop_name),
+                })?;
+            if !prop.unique.unwrap_or(false) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("property {} not unique", prop_name),
+                });
             }
+            let index_name = format!("_ux_{}", prop_name);
+            require_index_complete(storage, &index_name)?;
+            if let Some(id) = UniqueIndex::new(&index_name).may_load(storage, &prop.to_bytes(&value)?)? {
+                if accept(id)? {
+                    ids.push(id.into());
+                }
+            }
+        },
+    };
+
+    Ok((ids, next_cursor_info.map(|(key, id)| encode_cursor(key, id))))
+}
+
+/// Apply a property's index transforms to a bound on its index.
+fn transform_bound(
+    prop: &EntityProperty,
+    bound: IndexBound,
+) -> IndexBound {
+    match bound {
+        IndexBound::Inclusive(value) => IndexBound::Inclusive(prop.transform(&value, false)),
+        IndexBound::Exclusive(value) => IndexBound::Exclusive(prop.transform(&value, false)),
+    }
+}
+
+/// Fail if a job is still building an index, given its storage name.
+fn require_index_complete(
+    storage: &dyn Storage,
+    index_name: &str,
+) -> Result<(), ContractError> {
+    if let Some(job) = JOB.may_load(storage)? {
+        if job.incomplete_indices().iter().any(|x| x == index_name) {
+            return Err(ContractError::ValidationError {
+                reason: format!("index {} is being rebuilt", index_name),
+            });
         }
+    }
+    Ok(())
+}
+
+/// Load a property that can be scanned through its `_ix_` index, as the
+/// property its keys encode.
+fn load_indexed_property(
+    storage: &dyn Storage,
+    name: &str,
+) -> Result<EntityProperty, ContractError> {
+    let schema = SCHEMA.load(storage)?;
+    let prop = schema
+        .property(name)
+        .cloned()
+        .or_else(|| timestamp_property(name))
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: format!("property {} not found", name),
+        })?;
+    if !prop.indexed.unwrap_or(false) {
+        return Err(ContractError::ValidationError {
+            reason: format!("property {} not indexed", name),
+        });
+    }
+    require_index_complete(storage, &format!("_ix_{}", name))?;
+    // Bounds and cursors of an element index are element values
+    Ok(prop.index_property())
+}
+
+/// Scan an index between two bounds, appending accepted entity IDs and
+/// returning the last key and ID seen, from which the next cursor is built.
+/// The scan stops at `limit` accepted IDs or `MAX_SCAN_SIZE` keys, whichever
+/// comes first.
+#[allow(clippy::too_many_arguments)]
+fn scan_index(
+    storage: &dyn Storage,
+    index_name: &str,
+    start: Option<ScanBound>,
+    stop: Option<ScanBound>,
+    desc: bool,
+    limit: usize,
+    accept: &dyn Fn(u64) -> Result<bool, ContractError>,
+    ids: &mut Vec<Uint64>,
+) -> Result<Option<(Vec<u8>, u64)>, ContractError> {
+    let index = PropertyIndex::new(index_name);
+    let (lower, upper, order) = if desc {
+        (
+            stop.as_ref().map(|b| b.lower()),
+            start.as_ref().map(|b| b.upper()),
+            Order::Descending,
+        )
     } else {
-        for id in ids {
-            entities.push(Entity { id, data: None });
+        (
+            start.as_ref().map(|b| b.lower()),
+            stop.as_ref().map(|b| b.upper()),
+            Order::Ascending,
+        )
+    };
+    let mut last: Option<(Vec<u8>, u64)> = None;
+    for result in index.keys(storage, lower, upper, order).take(MAX_SCAN_SIZE) {
+        let (bytes, id) = result?;
+        last = Some((bytes, id));
+        // An element index can hold several keys for one entity
+        if ids.contains(&id.into()) {
+            continue;
+        }
+        if accept(id)? {
+            ids.push(id.into());
+            if ids.len() == limit {
+                break;
+            }
         }
     }
+    Ok(last)
+}
 
-    // Return results and the next cursor
-    Ok(ReadResponse {
-        entities,
-        cursor: next_cursor_info.and_then(|(key, id)| {
-            let mut bytes = EntityProperty::unpad(key);
-            bytes.extend(id.to_le_bytes());
-            Some(Binary::from(bytes))
-        }),
-    })
+/// One end of an index scan. `low` and `high` are the bound's key followed
+/// by the smallest and largest possible suffix for any trailing properties
+/// of a compound index; they're equal for single-property indexes.
+enum ScanBound {
+    Inclusive { low: Vec<u8>, high: Vec<u8> },
+    Exclusive { low: Vec<u8>, high: Vec<u8> },
+    Cursor((Vec<u8>, u64)),
+}
+
+impl ScanBound {
+    fn new(
+        key: Vec<u8>,
+        suffix_size: usize,
+        inclusive: bool,
+    ) -> Self {
+        let mut low = key.to_owned();
+        let mut high = key;
+        low.resize(low.len() + suffix_size, u8::MIN);
+        high.resize(high.len() + suffix_size, u8::MAX);
+        if inclusive {
+            Self::Inclusive { low, high }
+        } else {
+            Self::Exclusive { low, high }
+        }
+    }
+
+    fn from_index_bound(
+        prefix: &[u8],
+        prop: &EntityProperty,
+        bound: IndexBound,
+        suffix_size: usize,
+    ) -> Result<Self, ContractError> {
+        let (value, inclusive) = match bound {
+            IndexBound::Inclusive(v) => (v, true),
+            IndexBound::Exclusive(v) => (v, false),
+        };
+        let mut key = prefix.to_vec();
+        key.extend(prop.to_bytes(&value)?);
+        Ok(Self::new(key, suffix_size, inclusive))
+    }
+
+    fn lower(&self) -> Bound<'_, (&[u8], u64)> {
+        match self {
+            Self::Inclusive { low, .. } => Bound::inclusive((low.as_slice(), u64::MIN)),
+            Self::Exclusive { high, .. } => Bound::exclusive((high.as_slice(), u64::MAX)),
+            Self::Cursor((key, id)) => Bound::exclusive((key.as_slice(), *id)),
+        }
+    }
+
+    fn upper(&self) -> Bound<'_, (&[u8], u64)> {
+        match self {
+            Self::Inclusive { high, .. } => Bound::inclusive((high.as_slice(), u64::MAX)),
+            Self::Exclusive { low, .. } => Bound::exclusive((low.as_slice(), u64::MIN)),
+            Self::Cursor((key, id)) => Bound::exclusive((key.as_slice(), *id)),
+        }
+    }
+}
+
+/// A cursor is the unpadded index key followed by the big-endian entity ID.
+pub fn encode_cursor(
+    key: Vec<u8>,
+    id: u64,
+) -> Binary {
+    let mut bytes = EntityProperty::unpad(key);
+    bytes.extend(id.to_be_bytes());
+    Binary::from(bytes)
+}
+
+/// Split a cursor back into a full-width index key and entity ID, checking
+/// that the key decodes as a valid value of the property.
+pub fn decode_cursor(
+    prop: &EntityProperty,
+    cursor: &Binary,
+) -> Result<(Vec<u8>, u64), ContractError> {
+    let (key, id) = split_cursor(cursor, prop.key_size())?;
+    prop.from_bytes(&key)?;
+    Ok((key, id))
+}
+
+/// Split a cursor into an index key, zero-padded back out to `key_size`, and
+/// entity ID.
+pub fn split_cursor(
+    cursor: &Binary,
+    key_size: usize,
+) -> Result<(Vec<u8>, u64), ContractError> {
+    let id_size = size_of::<u64>();
+    if cursor.len() < id_size || cursor.len() - id_size > key_size {
+        return Err(ContractError::ValidationError {
+            reason: "invalid cursor".to_owned(),
+        });
+    }
+    let (key, id_bytes) = cursor.split_at(cursor.len() - id_size);
+    let mut key = key.to_vec();
+    key.resize(key_size, 0);
+    Ok((key, u64::from_be_bytes(id_bytes.try_into().unwrap())))
+}
+
+/// Decode a cursor that carries only an entity ID, as returned by targets
+/// that don't scan a property index.
+pub fn decode_id_cursor(cursor: &Binary) -> Result<u64, ContractError> {
+    let id_bytes: [u8; 8] = cursor
+        .as_slice()
+        .try_into()
+        .map_err(|_| ContractError::ValidationError {
+            reason: "invalid cursor".to_owned(),
+        })?;
+    Ok(u64::from_be_bytes(id_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn property(value: serde_json::Value) -> EntityProperty {
+        serde_json::from_value(json!({"name": "x", "indexed": true, "value": value})).unwrap()
+    }
+
+    #[test]
+    fn cursor_round_trips_keys_with_trailing_zeros() {
+        for (value, v) in [
+            (json!({"u64": {}}), json!(256)),
+            (json!({"u64": {}}), json!(0)),
+            (json!({"i64": {}}), json!(-256)),
+            (json!({"i128": {}}), json!("0")),
+            (json!({"string": {}}), json!("ab")),
+        ] {
+            let prop = property(value);
+            let key = prop.to_bytes(&v).unwrap();
+            let cursor = encode_cursor(key.to_owned(), 7);
+            assert_eq!(decode_cursor(&prop, &cursor).unwrap(), (key, 7));
+        }
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        let prop = property(json!({"u16": {}}));
+        assert!(decode_cursor(&prop, &Binary::from(vec![0; 7])).is_err());
+        assert!(decode_cursor(&prop, &Binary::from(vec![0; 11])).is_err());
+        let prop = property(json!({"enum": {"values": ["a", "b"]}}));
+        let cursor = encode_cursor(vec![0, 2], 1);
+        assert!(decode_cursor(&prop, &cursor).is_err());
+        assert_eq!(decode_id_cursor(&Binary::from(9u64.to_be_bytes())).unwrap(), 9);
+        assert!(decode_id_cursor(&Binary::from(vec![0; 9])).is_err());
+    }
 }
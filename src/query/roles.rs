@@ -0,0 +1,37 @@
+use cosmwasm_std::Order;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::RolesArgs,
+    query::read::MAX_PAGE_SIZE,
+    responses::{RoleGrantEntry, RolesResponse},
+    state::{QueryContext, ROLES},
+};
+
+pub fn query_roles(
+    ctx: QueryContext,
+    args: RolesArgs,
+) -> Result<RolesResponse, ContractError> {
+    let QueryContext { deps, .. } = ctx;
+    let RolesArgs { cursor, limit } = args;
+    let limit = limit.unwrap_or(10).min(MAX_PAGE_SIZE) as usize;
+    let min = cursor.as_ref().map(Bound::exclusive);
+
+    let grants = ROLES
+        .range(deps.storage, min, None, Order::Ascending)
+        .take(limit)
+        .map(|r| -> Result<_, ContractError> {
+            let (address, grant) = r?;
+            Ok(RoleGrantEntry { address, grant })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cursor = if grants.len() == limit {
+        grants.last().map(|e| e.address.to_owned())
+    } else {
+        None
+    };
+
+    Ok(RolesResponse { grants, cursor })
+}
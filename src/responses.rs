@@ -1,13 +1,51 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Binary, Uint64};
+use cosmwasm_std::{Addr, Binary, Int256, SignedDecimal256, Timestamp, Uint64};
 use serde_json;
+use std::collections::BTreeMap;
 
-use crate::{schema::EntitySchema, state::CollectionMetadata};
+use crate::{
+    schema::EntitySchema,
+    state::{CollectionConfig, CollectionMetadata, Job, OperatorProposal, RoleGrant, Validity},
+};
 
 #[cw_serde]
 pub struct Entity {
     pub id: Uint64,
+    pub owner: Option<Addr>,
     pub data: Option<serde_json::Value>,
+    pub timestamps: Option<EntityTimestamps>,
+    /// Referenced entities inlined by reference property, if expanded. Left
+    /// out once a read has inlined as many entities as it may.
+    pub expanded: Option<BTreeMap<String, Entity>>,
+}
+
+/// When an entity was created and last written, and its validity window if
+/// it has one. Heights are missing for entities written before they were
+/// recorded.
+#[cw_serde]
+pub struct EntityTimestamps {
+    pub created_at: Timestamp,
+    pub created_height: Option<u64>,
+    pub updated_at: Timestamp,
+    pub updated_height: Option<u64>,
+    pub validity: Option<Validity>,
+}
+
+#[cw_serde]
+pub struct CreateResponse {
+    pub id: Uint64,
+}
+
+#[cw_serde]
+pub enum BatchOperationResult {
+    Create { id: Uint64 },
+    Update { id: Uint64 },
+    Delete { id: Uint64 },
+}
+
+#[cw_serde]
+pub struct BatchResponse {
+    pub results: Vec<BatchOperationResult>,
 }
 
 #[cw_serde]
@@ -19,7 +57,87 @@ pub struct ReadResponse {
 #[cw_serde]
 pub struct InfoResponse {
     pub metadata: CollectionMetadata,
+    pub config: CollectionConfig,
     pub schema: EntitySchema,
     pub operator: Addr,
     pub size: u32,
 }
+
+#[cw_serde]
+pub struct PendingOperatorResponse {
+    pub proposal: OperatorProposal,
+    pub is_expired: bool,
+}
+
+#[cw_serde]
+pub struct RoleGrantEntry {
+    pub address: Addr,
+    pub grant: RoleGrant,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub grants: Vec<RoleGrantEntry>,
+    pub cursor: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct PermissionsResponse {
+    pub address: Addr,
+    pub is_operator: bool,
+    pub grant: Option<RoleGrant>,
+    pub can_create: bool,
+    pub can_update: bool,
+    pub can_delete: bool,
+    pub can_manage_roles: bool,
+}
+
+#[cw_serde]
+pub struct JobResponse {
+    pub job: Job,
+    pub incomplete_indices: Vec<String>,
+}
+
+/// Running totals of an aggregate. When aggregating a property, only
+/// entities that have it are counted.
+#[cw_serde]
+#[derive(Default)]
+pub struct Accumulator {
+    pub count: Uint64,
+    pub sum: Int256,
+    pub min: Option<Int256>,
+    pub max: Option<Int256>,
+}
+
+/// Totals so far, which are partial while a cursor is returned.
+#[cw_serde]
+pub struct AggregateResponse {
+    pub accumulator: Accumulator,
+    pub average: Option<SignedDecimal256>,
+    pub cursor: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct HistoryResponse {
+    pub id: Uint64,
+    pub revision: u32,
+    pub revisions: Vec<RevisionEntry>,
+    pub cursor: Option<u32>,
+}
+
+/// A version of an entity, with the changes made by it if the revision
+/// before it is still retained.
+#[cw_serde]
+pub struct RevisionEntry {
+    pub revision: u32,
+    pub time: Timestamp,
+    pub data: serde_json::Value,
+    pub changes: Option<Vec<PropertyChange>>,
+}
+
+#[cw_serde]
+pub struct PropertyChange {
+    pub property: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
@@ -1,31 +1,90 @@
 use cosmwasm_schema::cw_serde;
-use std::mem::size_of;
+use cosmwasm_std::{Addr, Api, Binary, Int256};
+use std::{cmp::Ordering, collections::BTreeSet, mem::size_of};
+use unicode_normalization::UnicodeNormalization;
 
-use crate::error::ContractError;
+use crate::{error::ContractError, msg::Filter};
 
 pub const DEFAULT_PADDING_STRING: u16 = 1024;
 pub const DEFAULT_PADDING_ARRAY: u16 = 1024;
 pub const DEFAULT_PADDING_OBJECT: u16 = 1024;
+pub const DEFAULT_PADDING_BINARY: u16 = 1024;
+/// Bech32 strings are at most 90 characters
+pub const PADDING_ADDRESS: u16 = 90;
+pub const DEFAULT_DECIMAL_SCALE: u8 = 18;
+pub const MAX_DECIMAL_SCALE: u8 = 38;
+/// Longer words are indexed and searched by their leading bytes
+pub const MAX_TOKEN_BYTES: usize = 32;
 
 #[cw_serde]
 pub struct EntitySchema {
     pub name: String,
     pub properties: Vec<EntityProperty>,
+    pub unknown_properties: Option<UnknownPropertyPolicy>,
+    pub max_byte_size: Option<u32>,
+    pub indexes: Option<Vec<CompoundIndex>>,
+}
+
+/// An index over an ordered list of properties, keyed by the concatenation
+/// of each property's index key.
+#[cw_serde]
+pub struct CompoundIndex {
+    pub name: String,
+    pub properties: Vec<String>,
+}
+
+/// What to do with entity fields that aren't declared in the schema.
+#[cw_serde]
+pub enum UnknownPropertyPolicy {
+    Reject,
+    Allow,
+    Strip,
 }
 
 #[cw_serde]
 pub struct EntityProperty {
     pub indexed: Option<bool>,
     pub required: Option<bool>,
+    pub unique: Option<bool>,
+    /// Transforms applied in order to a value before it's written to the
+    /// `_ix_` index, and to query values compared against it
+    pub index_transforms: Option<Vec<IndexTransform>>,
+    /// Keep a full-text `_tx_` index from each word of a string to entities
+    pub searchable: Option<bool>,
+    pub default: Option<serde_json::Value>,
     pub value: EntityPropertyParams,
     pub name: String,
 }
 
+#[cw_serde]
+pub enum IndexTransform {
+    Lowercase,
+    Trim,
+    /// Unicode NFKC normalization
+    Normalize,
+    /// Number of characters of a string or elements of an array, indexed as
+    /// a u32. Must be the last transform.
+    Length,
+}
+
 #[cw_serde]
 pub enum EntityPropertyParams {
-    Array { max_byte_size: Option<u16> },
-    Object { max_byte_size: Option<u16> },
-    String { max_byte_size: Option<u16> },
+    Array {
+        max_byte_size: Option<u16>,
+        /// Type of the array's elements, checked on write
+        items: Option<Box<EntityPropertyParams>>,
+        /// Index each distinct element rather than the array as a whole, so
+        /// lookups find every array containing a value. Requires `items`.
+        index_elements: Option<bool>,
+    },
+    Object {
+        max_byte_size: Option<u16>,
+        /// Fields of the object, which may be indexed by their dotted path
+        properties: Option<Vec<EntityProperty>>,
+    },
+    String {
+        max_byte_size: Option<u16>,
+    },
     U8 {},
     U16 {},
     U32 {},
@@ -37,56 +96,797 @@ pub enum EntityPropertyParams {
     I64 {},
     I128 {},
     Bool {},
+    /// Account or contract address, checked with the chain's address format
+    Address {},
+    /// Nanoseconds since the epoch, as a string like `cosmwasm_std::Timestamp`
+    Timestamp {},
+    /// Fixed-point number with `scale` fractional digits, 18 by default,
+    /// written as a string like "-12.5"
+    Decimal {
+        scale: Option<u8>,
+    },
+    /// One of a list of strings, ordered as listed
+    Enum {
+        values: Vec<String>,
+    },
+    /// Base64-encoded bytes
+    Binary {
+        max_byte_size: Option<u16>,
+    },
+    /// Finite floating-point number, written as a string like "-1.5e-3"
+    /// since contract JSON has no fractions, and ordered like `f64::total_cmp`
+    F64 {},
+    /// ID of an entity in this collection, or in another cw-entity contract,
+    /// which must exist when written. Entities referencing an entity are
+    /// kept in a `_rx_` index.
+    Ref {
+        contract: Option<Addr>,
+        /// What deleting the referenced entity does to entities referencing
+        /// it, restrict by default. Only for references within the collection.
+        on_delete: Option<RefPolicy>,
+    },
+}
+
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum RefPolicy {
+    /// Refuse to delete a referenced entity
+    #[default]
+    Restrict,
+    /// Delete the entities referencing it too
+    Cascade,
+    /// Remove the reference from the entities referencing it
+    SetNull,
 }
 
 impl EntitySchema {
-    // pub fn validate(
-    //     &self,
-    //     entity: serde_json::Value,
-    // ) {
-    //     todo!()
-    // }
+    /// Validate a complete entity against the schema, returning the data to
+    /// store. Every offending property is reported in a single error.
+    pub fn validate(
+        &self,
+        api: &dyn Api,
+        entity: &serde_json::Value,
+    ) -> Result<serde_json::Value, ContractError> {
+        let values = entity.as_object().ok_or_else(|| ContractError::ValidationError {
+            reason: "entity is not an object".to_owned(),
+        })?;
+
+        let mut errors: Vec<String> = vec![];
+        let mut output = serde_json::Map::with_capacity(values.len());
+
+        for prop in self.properties.iter() {
+            if let Some(value) = values.get(&prop.name) {
+                match prop.validate(api, value) {
+                    Err(ContractError::ValidationError { reason }) => errors.push(reason),
+                    Err(e) => return Err(e),
+                    Ok(_) => {},
+                }
+            } else if let Some(default) = &prop.default {
+                output.insert(prop.name.to_owned(), default.to_owned());
+            } else if prop.required.unwrap_or(false) {
+                errors.push(format!("{} - required", prop.name));
+            }
+        }
+
+        let policy = self
+            .unknown_properties
+            .to_owned()
+            .unwrap_or(UnknownPropertyPolicy::Allow);
+        for (k, v) in values.iter() {
+            if !self.properties.iter().any(|p| p.name == *k) {
+                // It would read like a path to a nested field
+                if k.contains('.') {
+                    errors.push(format!("{} - unknown property with a dot in its name", k));
+                    continue;
+                }
+                match policy {
+                    UnknownPropertyPolicy::Reject => errors.push(format!("{} - unknown property", k)),
+                    UnknownPropertyPolicy::Strip => continue,
+                    UnknownPropertyPolicy::Allow => {},
+                }
+            }
+            output.insert(k.to_owned(), v.to_owned());
+        }
+
+        let output = serde_json::Value::Object(output);
+
+        if let Some(max_byte_size) = self.max_byte_size {
+            if output.to_string().len() > max_byte_size as usize {
+                errors.push(format!("{} - beyond allowed max byte size", self.name));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ContractError::ValidationError {
+                reason: errors.join("; "),
+            });
+        }
+
+        Ok(output)
+    }
+
+    /// Find a property by name, or a nested object field by its dotted path
+    /// like `profile.country`.
+    pub fn property(
+        &self,
+        path: &str,
+    ) -> Option<&EntityProperty> {
+        let mut names = path.split('.');
+        let first = names.next()?;
+        let mut prop = self.properties.iter().find(|p| p.name == first)?;
+        for name in names {
+            prop = prop.fields()?.iter().find(|p| p.name == name)?;
+        }
+        Some(prop)
+    }
+
+    /// Every property and nested object field, with its dotted path.
+    pub fn paths(&self) -> Vec<(String, &EntityProperty)> {
+        fn collect<'a>(
+            prefix: &str,
+            props: &'a [EntityProperty],
+            out: &mut Vec<(String, &'a EntityProperty)>,
+        ) {
+            for prop in props {
+                let path = format!("{}{}", prefix, prop.name);
+                if let Some(fields) = prop.fields() {
+                    collect(&format!("{}.", path), fields, out);
+                }
+                out.push((path, prop));
+            }
+        }
+        let mut out = vec![];
+        collect("", &self.properties, &mut out);
+        out
+    }
+
+    pub fn compound_index(
+        &self,
+        name: &str,
+    ) -> Option<&CompoundIndex> {
+        self.indexes.iter().flatten().find(|ix| ix.name == name)
+    }
+
+    /// Check that compound indexes are uniquely named and only refer to
+    /// properties in the schema, and that property definitions are sound,
+    /// with defaults that fit them.
+    pub fn validate_indexes(
+        &self,
+        api: &dyn Api,
+    ) -> Result<(), ContractError> {
+        let indexes = self.indexes.to_owned().unwrap_or_default();
+        for (i, index) in indexes.iter().enumerate() {
+            if index.properties.is_empty() {
+                return Err(ContractError::ValidationError {
+                    reason: format!("index {} has no properties", index.name),
+                });
+            }
+            if indexes[..i].iter().any(|ix| ix.name == index.name) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("index {} defined more than once", index.name),
+                });
+            }
+            for name in index.properties.iter() {
+                if self.property(name).is_none() {
+                    return Err(ContractError::ValidationError {
+                        reason: format!("index {} refers to unknown property {}", index.name, name),
+                    });
+                }
+            }
+        }
+        for (path, prop) in self.paths() {
+            // Dots separate the names along a path to a nested field
+            if prop.name.is_empty() || prop.name.contains('.') {
+                return Err(ContractError::ValidationError {
+                    reason: format!("{} - invalid property name", path),
+                });
+            }
+            if let EntityPropertyParams::Array {
+                items: None,
+                index_elements: Some(true),
+                ..
+            } = prop.value
+            {
+                return Err(ContractError::ValidationError {
+                    reason: format!("{} - element index needs an item type", path),
+                });
+            }
+            prop.validate_index_transforms(&path)?;
+            prop.validate_params(&path)?;
+            if let Some(item) = prop.item_property() {
+                if let EntityPropertyParams::Ref { .. } = item.value {
+                    return Err(ContractError::ValidationError {
+                        reason: format!("{} - references can't be array items", path),
+                    });
+                }
+                item.validate_params(&path)?;
+            }
+            if prop.searchable.unwrap_or(false) && !matches!(prop.value, EntityPropertyParams::String { .. }) {
+                return Err(ContractError::ValidationError {
+                    reason: format!("{} - only strings are searchable", path),
+                });
+            }
+            if let Some(default) = &prop.default {
+                match prop.validate(api, default) {
+                    Err(ContractError::ValidationError { reason }) => {
+                        return Err(ContractError::ValidationError {
+                            reason: format!("{} - invalid default: {}", path, reason),
+                        })
+                    },
+                    Err(e) => return Err(e),
+                    Ok(_) => {},
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compound index key for an entity, or None if it lacks any of the
+    /// index's properties.
+    pub fn compound_key(
+        &self,
+        index: &CompoundIndex,
+        values: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Option<Vec<u8>>, ContractError> {
+        let mut key: Vec<u8> = vec![];
+        for name in index.properties.iter() {
+            match (self.property(name), lookup(values, name)) {
+                (Some(prop), Some(value)) => key.extend(prop.to_bytes(value)?),
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(key))
+    }
+
+    /// Evaluate a filter against a stored entity.
+    pub fn matches(
+        &self,
+        filter: &Filter,
+        entity: &serde_json::Value,
+    ) -> Result<bool, ContractError> {
+        let cmp = |property: &str, value: &serde_json::Value| self.compare(property, entity, value);
+        Ok(match filter {
+            Filter::And(filters) => {
+                for f in filters {
+                    if !self.matches(f, entity)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            },
+            Filter::Or(filters) => {
+                for f in filters {
+                    if self.matches(f, entity)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            },
+            Filter::Not(f) => !self.matches(f, entity)?,
+            Filter::Eq { property, value } => cmp(property, value)? == Some(Ordering::Equal),
+            Filter::Ne { property, value } => cmp(property, value)?.is_some_and(|o| o != Ordering::Equal),
+            Filter::Lt { property, value } => cmp(property, value)? == Some(Ordering::Less),
+            Filter::Lte { property, value } => cmp(property, value)?.is_some_and(|o| o != Ordering::Greater),
+            Filter::Gt { property, value } => cmp(property, value)? == Some(Ordering::Greater),
+            Filter::Gte { property, value } => cmp(property, value)?.is_some_and(|o| o != Ordering::Less),
+            Filter::In { property, values } => {
+                for value in values {
+                    if cmp(property, value)? == Some(Ordering::Equal) {
+                        return Ok(true);
+                    }
+                }
+                false
+            },
+            Filter::Exists { property } => lookup_value(entity, property).is_some_and(|v| !v.is_null()),
+            Filter::Contains { property, value } => match (lookup_value(entity, property), value) {
+                (Some(serde_json::Value::String(s)), serde_json::Value::String(sub)) => s.contains(sub.as_str()),
+                (Some(serde_json::Value::Array(items)), _) => items.contains(value),
+                _ => false,
+            },
+        })
+    }
+
+    /// Compare an entity's value for a property with a filter operand, in the
+    /// order the property's index sorts them. None if the entity lacks the
+    /// property or the two values have no order, like a pair of objects.
+    fn compare(
+        &self,
+        name: &str,
+        entity: &serde_json::Value,
+        operand: &serde_json::Value,
+    ) -> Result<Option<Ordering>, ContractError> {
+        let value = match lookup_value(entity, name) {
+            Some(v) if !v.is_null() => v,
+            _ => return Ok(None),
+        };
+        let equal_or_none = |a: &serde_json::Value, b: &serde_json::Value| (a == b).then_some(Ordering::Equal);
+        let prop = match self.property(name) {
+            Some(prop) => prop,
+            // Undeclared properties compare by JSON type
+            None => {
+                return Ok(match (value, operand) {
+                    (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
+                        a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b))
+                    },
+                    (serde_json::Value::String(a), serde_json::Value::String(b)) => Some(a.cmp(b)),
+                    (serde_json::Value::Bool(a), serde_json::Value::Bool(b)) => Some(a.cmp(b)),
+                    (a, b) => equal_or_none(a, b),
+                })
+            },
+        };
+        Ok(match prop.value {
+            EntityPropertyParams::String { .. } => Some(prop.expect_str(value)?.cmp(prop.expect_str(operand)?)),
+            EntityPropertyParams::Array { .. } | EntityPropertyParams::Object { .. } => equal_or_none(value, operand),
+            _ => Some(prop.to_bytes(value)?.cmp(&prop.to_bytes(operand)?)),
+        })
+    }
+
+    pub fn property_mut(
+        &mut self,
+        path: &str,
+    ) -> Result<&mut EntityProperty, ContractError> {
+        let not_found = || ContractError::NotFound {
+            reason: format!("property {} not found", path),
+        };
+        if let Some(i) = self.properties.iter().position(|p| p.name == path) {
+            return Ok(&mut self.properties[i]);
+        }
+        let mut names = path.split('.');
+        let first = names.next().ok_or_else(not_found)?;
+        let mut prop = self
+            .properties
+            .iter_mut()
+            .find(|p| p.name == first)
+            .ok_or_else(not_found)?;
+        for name in names {
+            prop = match &mut prop.value {
+                EntityPropertyParams::Object {
+                    properties: Some(fields),
+                    ..
+                } => fields.iter_mut().find(|p| p.name == name).ok_or_else(not_found)?,
+                _ => return Err(not_found()),
+            };
+        }
+        Ok(prop)
+    }
+}
+
+/// Write a count of units of 10^-scale as a decimal string.
+fn format_decimal(
+    atomics: i128,
+    scale: u8,
+) -> String {
+    let sign = if atomics < 0 { "-" } else { "" };
+    let divisor = 10u128.pow(scale as u32);
+    let (whole, fraction) = (atomics.unsigned_abs() / divisor, atomics.unsigned_abs() % divisor);
+    if scale == 0 {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{:0>width$}", sign, whole, fraction, width = scale as usize)
+    }
+}
+
+/// Remove a property, or a nested object field by its dotted path, from an
+/// entity's data.
+pub fn remove_path(
+    values: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+) -> Option<serde_json::Value> {
+    let Some((parent, name)) = path.rsplit_once('.') else {
+        return values.remove(path);
+    };
+    let mut names = parent.split('.');
+    let mut obj = values.get_mut(names.next()?)?.as_object_mut()?;
+    for name in names {
+        obj = obj.get_mut(name)?.as_object_mut()?;
+    }
+    obj.remove(name)
+}
+
+/// Split text into distinct lowercase words for a token index, cutting each
+/// down to `MAX_TOKEN_BYTES`.
+pub fn tokenize(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut token = String::with_capacity(word.len());
+            for c in word.chars().flat_map(char::to_lowercase) {
+                if token.len() + c.len_utf8() > MAX_TOKEN_BYTES {
+                    break;
+                }
+                token.push(c);
+            }
+            token
+        })
+        .collect()
+}
+
+/// Find the value of a property, or of a nested object field by its dotted
+/// path, in an entity's data.
+pub fn lookup<'v>(
+    values: &'v serde_json::Map<String, serde_json::Value>,
+    path: &str,
+) -> Option<&'v serde_json::Value> {
+    let mut names = path.split('.');
+    let mut value = values.get(names.next()?)?;
+    for name in names {
+        value = value.as_object()?.get(name)?;
+    }
+    Some(value)
+}
+
+pub fn lookup_value<'v>(
+    entity: &'v serde_json::Value,
+    path: &str,
+) -> Option<&'v serde_json::Value> {
+    entity.as_object().and_then(|values| lookup(values, path))
 }
 
 impl EntityProperty {
+    /// A property describing the elements of an array property, if it
+    /// declares an item type.
+    pub fn item_property(&self) -> Option<EntityProperty> {
+        match &self.value {
+            EntityPropertyParams::Array { items: Some(items), .. } => Some(EntityProperty {
+                indexed: None,
+                required: None,
+                unique: None,
+                index_transforms: None,
+                searchable: None,
+                default: None,
+                value: *items.to_owned(),
+                name: self.name.to_owned(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The property whose encoding keys this property's `_ix_` index, which
+    /// is the item property for an element index, and a u32 for a length
+    /// index. It keeps the index transforms, so `index_key` applies them.
+    pub fn index_property(&self) -> EntityProperty {
+        let mut prop = match self.element_indexed() {
+            Some(item) => item,
+            None => self.to_owned(),
+        };
+        prop.index_transforms = self.index_transforms.to_owned();
+        if let Some(IndexTransform::Length) = self.index_transforms.iter().flatten().last() {
+            prop.value = EntityPropertyParams::U32 {};
+        }
+        prop
+    }
+
+    /// Keys for a value in this property's `_ix_` index: one for the value,
+    /// or one per distinct element for an element index.
+    pub fn index_keys(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<BTreeSet<Vec<u8>>, ContractError> {
+        let prop = self.index_property();
+        match self.element_indexed() {
+            Some(_) => value
+                .as_array()
+                .ok_or_else(|| self.validation_error("expected array").unwrap_err())?
+                .iter()
+                .map(|element| prop.index_key(element))
+                .collect(),
+            None => Ok(BTreeSet::from([prop.index_key(value)?])),
+        }
+    }
+
+    /// Encode a stored or queried value as a key of an index property,
+    /// transforming it first. Values already of the transformed type, like a
+    /// number queried against a length index, are encoded as they are.
+    pub fn index_key(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<Vec<u8>, ContractError> {
+        self.to_bytes(&self.transform(value, false))
+    }
+
+    /// Apply the index transforms to a value. For a prefix, only leading
+    /// whitespace is trimmed, since trailing whitespace may precede more
+    /// characters of the value.
+    pub fn transform(
+        &self,
+        value: &serde_json::Value,
+        prefix: bool,
+    ) -> serde_json::Value {
+        let mut value = value.to_owned();
+        for transform in self.index_transforms.iter().flatten() {
+            value = match (transform, value) {
+                (IndexTransform::Lowercase, serde_json::Value::String(s)) => s.to_lowercase().into(),
+                (IndexTransform::Trim, serde_json::Value::String(s)) if prefix => s.trim_start().into(),
+                (IndexTransform::Trim, serde_json::Value::String(s)) => s.trim().into(),
+                (IndexTransform::Normalize, serde_json::Value::String(s)) => s.nfkc().collect::<String>().into(),
+                (IndexTransform::Length, serde_json::Value::String(s)) => s.chars().count().into(),
+                (IndexTransform::Length, serde_json::Value::Array(a)) => a.len().into(),
+                (_, value) => value,
+            };
+        }
+        value
+    }
+
+    /// Check the parameters of the property's type.
+    fn validate_params(
+        &self,
+        path: &str,
+    ) -> Result<(), ContractError> {
+        let error = |reason: &str| {
+            Err(ContractError::ValidationError {
+                reason: format!("{} - {}", path, reason),
+            })
+        };
+        match &self.value {
+            EntityPropertyParams::Decimal { scale: Some(scale) } if *scale > MAX_DECIMAL_SCALE => {
+                error(&format!("scale above {}", MAX_DECIMAL_SCALE))
+            },
+            EntityPropertyParams::Enum { values } if values.is_empty() || values.len() > u16::MAX as usize => {
+                error("invalid number of enum values")
+            },
+            EntityPropertyParams::Enum { values } if values.iter().collect::<BTreeSet<_>>().len() != values.len() => {
+                error("duplicate enum values")
+            },
+            EntityPropertyParams::Ref {
+                contract: Some(_),
+                on_delete: Some(_),
+            } => error("delete policy only applies within the collection"),
+            EntityPropertyParams::Ref {
+                on_delete: Some(RefPolicy::SetNull),
+                ..
+            } if self.required.unwrap_or(false) => error("required reference can't be set to null"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the index transforms apply to the type of value indexed.
+    fn validate_index_transforms(
+        &self,
+        path: &str,
+    ) -> Result<(), ContractError> {
+        let Some(transforms) = &self.index_transforms else {
+            return Ok(());
+        };
+        let value = self
+            .element_indexed()
+            .map(|item| item.value)
+            .unwrap_or(self.value.to_owned());
+        for (i, transform) in transforms.iter().enumerate() {
+            let applies = match transform {
+                IndexTransform::Length => {
+                    i == transforms.len() - 1
+                        && matches!(
+                            value,
+                            EntityPropertyParams::String { .. } | EntityPropertyParams::Array { .. }
+                        )
+                },
+                _ => matches!(value, EntityPropertyParams::String { .. }),
+            };
+            if !applies {
+                return Err(ContractError::ValidationError {
+                    reason: format!("{} - cannot index by {:?}", path, transform),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys for a value in this property's `_tx_` token index.
+    pub fn search_tokens(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<BTreeSet<String>, ContractError> {
+        Ok(tokenize(self.expect_str(value)?))
+    }
+
+    /// The item property, if this is an array indexed by element.
+    fn element_indexed(&self) -> Option<EntityProperty> {
+        match self.value {
+            EntityPropertyParams::Array {
+                index_elements: Some(true),
+                ..
+            } => self.item_property(),
+            _ => None,
+        }
+    }
+
+    /// Nested fields of an object property, if it declares any.
+    pub fn fields(&self) -> Option<&Vec<EntityProperty>> {
+        match &self.value {
+            EntityPropertyParams::Object {
+                properties: Some(fields),
+                ..
+            } => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Encode a value as an index key. Every variant produces a fixed-width key
+    /// whose byte order matches the natural order of the values: integers are
+    /// written big-endian with the sign bit flipped, and variable-size values
+    /// are zero-padded out to their max byte size.
     pub fn to_bytes(
         &self,
         value: &serde_json::Value,
     ) -> Result<Vec<u8>, ContractError> {
-        // NOTE: This assumes that we've already validated.
-        Ok(match self.value {
-            EntityPropertyParams::String { .. } => self.pad(value.as_str().unwrap().as_bytes().to_vec())?,
+        Ok(match &self.value {
+            EntityPropertyParams::String { .. } | EntityPropertyParams::Address {} => {
+                self.pad(self.expect_str(value)?.as_bytes().to_vec())?
+            },
             EntityPropertyParams::Array { .. } => self.pad(value.to_string().as_bytes().to_vec())?,
             EntityPropertyParams::Object { .. } => self.pad(value.to_string().as_bytes().to_vec())?,
-            EntityPropertyParams::U8 {} => value.as_u64().unwrap().clamp(0, u8::MAX as u64).to_le_bytes()[..1].to_vec(),
-            EntityPropertyParams::U16 {} => {
-                value.as_u64().unwrap().clamp(0, u16::MAX as u64).to_le_bytes()[..2].to_vec()
+            EntityPropertyParams::U8 {} => self.expect_int::<u8>(value)?.to_be_bytes().to_vec(),
+            EntityPropertyParams::U16 {} => self.expect_int::<u16>(value)?.to_be_bytes().to_vec(),
+            EntityPropertyParams::U32 {} => self.expect_int::<u32>(value)?.to_be_bytes().to_vec(),
+            EntityPropertyParams::U64 {} => self.expect_int::<u64>(value)?.to_be_bytes().to_vec(),
+            EntityPropertyParams::U128 {} => self.expect_u128(value)?.to_be_bytes().to_vec(),
+            EntityPropertyParams::I8 {} => (self.expect_int::<i8>(value)? ^ i8::MIN).to_be_bytes().to_vec(),
+            EntityPropertyParams::I16 {} => (self.expect_int::<i16>(value)? ^ i16::MIN).to_be_bytes().to_vec(),
+            EntityPropertyParams::I32 {} => (self.expect_int::<i32>(value)? ^ i32::MIN).to_be_bytes().to_vec(),
+            EntityPropertyParams::I64 {} => (self.expect_int::<i64>(value)? ^ i64::MIN).to_be_bytes().to_vec(),
+            EntityPropertyParams::I128 {} => (self.expect_int::<i128>(value)? ^ i128::MIN).to_be_bytes().to_vec(),
+            EntityPropertyParams::Bool {} => vec![if self.expect_bool(value)? { 1u8 } else { 0u8 }],
+            EntityPropertyParams::Timestamp {} | EntityPropertyParams::Ref { .. } => {
+                self.expect_int::<u64>(value)?.to_be_bytes().to_vec()
             },
-            EntityPropertyParams::U32 {} => {
-                value.as_u64().unwrap().clamp(0, u32::MAX as u64).to_le_bytes()[..4].to_vec()
+            EntityPropertyParams::Decimal { scale } => {
+                (self.expect_decimal(value, scale.unwrap_or(DEFAULT_DECIMAL_SCALE))? ^ i128::MIN)
+                    .to_be_bytes()
+                    .to_vec()
             },
-            EntityPropertyParams::U64 {} => value.as_u64().unwrap().clamp(0, u64::MAX).to_le_bytes()[..8].to_vec(),
-            EntityPropertyParams::U128 {} => self.pad(value.as_str().unwrap().as_bytes().to_vec())?,
-            EntityPropertyParams::I8 {} => value.as_i64().unwrap().clamp(0, i8::MAX as i64).to_le_bytes()[..1].to_vec(),
-            EntityPropertyParams::I16 {} => {
-                value.as_i64().unwrap().clamp(0, i16::MAX as i64).to_le_bytes()[..2].to_vec()
+            EntityPropertyParams::Enum { values } => {
+                let s = self.expect_str(value)?;
+                let i = values
+                    .iter()
+                    .position(|v| v == s)
+                    .ok_or_else(|| self.validation_error("not an allowed value").unwrap_err())?;
+                (i as u16).to_be_bytes().to_vec()
             },
-            EntityPropertyParams::I32 {} => {
-                value.as_i64().unwrap().clamp(0, i32::MAX as i64).to_le_bytes()[..4].to_vec()
+            // Zero padding alone would make values differing only by trailing
+            // zeros equal, so the length follows it
+            EntityPropertyParams::Binary { max_byte_size } => {
+                let mut key = self.expect_binary(value)?;
+                let len = key.len() as u16;
+                let max_byte_size = max_byte_size.unwrap_or(DEFAULT_PADDING_BINARY) as usize;
+                if key.len() > max_byte_size {
+                    return Err(self.validation_error("beyond allowed max byte size").unwrap_err());
+                }
+                key.resize(max_byte_size, 0);
+                key.extend(len.to_be_bytes());
+                key
+            },
+            // Flipping the sign bit of positive numbers, and every bit of
+            // negative ones, orders the bits like `f64::total_cmp`
+            EntityPropertyParams::F64 {} => {
+                let bits = self.expect_f64(value)?.to_bits();
+                let mask = if bits >> 63 == 1 { u64::MAX } else { 1 << 63 };
+                (bits ^ mask).to_be_bytes().to_vec()
+            },
+        })
+    }
+
+    /// Read the entity ID held by a reference property.
+    pub fn ref_id(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<u64, ContractError> {
+        match self.value {
+            EntityPropertyParams::Ref { .. } => self.expect_int::<u64>(value),
+            _ => Err(self.validation_error("not a reference").unwrap_err()),
+        }
+    }
+
+    /// Whether values of the property can be read with `to_int256`.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self.value,
+            EntityPropertyParams::U8 {}
+                | EntityPropertyParams::U16 {}
+                | EntityPropertyParams::U32 {}
+                | EntityPropertyParams::U64 {}
+                | EntityPropertyParams::U128 {}
+                | EntityPropertyParams::I8 {}
+                | EntityPropertyParams::I16 {}
+                | EntityPropertyParams::I32 {}
+                | EntityPropertyParams::I64 {}
+                | EntityPropertyParams::I128 {}
+                | EntityPropertyParams::Timestamp {}
+                | EntityPropertyParams::Decimal { .. }
+        )
+    }
+
+    /// Read a value of an integer, timestamp or decimal property as a number
+    /// wide enough for sums of any of them. Timestamps are read in
+    /// nanoseconds, and decimals in units of their last fractional digit.
+    pub fn to_int256(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<Int256, ContractError> {
+        Ok(match self.value {
+            EntityPropertyParams::U128 {} => self.expect_u128(value)?.into(),
+            EntityPropertyParams::U8 {}
+            | EntityPropertyParams::U16 {}
+            | EntityPropertyParams::U32 {}
+            | EntityPropertyParams::U64 {}
+            | EntityPropertyParams::I8 {}
+            | EntityPropertyParams::I16 {}
+            | EntityPropertyParams::I32 {}
+            | EntityPropertyParams::I64 {}
+            | EntityPropertyParams::I128 {}
+            | EntityPropertyParams::Timestamp {} => self.expect_int::<i128>(value)?.into(),
+            EntityPropertyParams::Decimal { scale } => self
+                .expect_decimal(value, scale.unwrap_or(DEFAULT_DECIMAL_SCALE))?
+                .into(),
+            _ => return Err(self.validation_error("not numeric").unwrap_err()),
+        })
+    }
+
+    /// Decode an index key produced by `to_bytes` back into its JSON value.
+    /// 128-bit integers are returned as strings, like they are stored.
+    pub fn from_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Result<serde_json::Value, ContractError> {
+        if bytes.len() != self.key_size() {
+            return Err(self.validation_error("invalid index key length").unwrap_err());
+        }
+        Ok(match &self.value {
+            EntityPropertyParams::String { .. } | EntityPropertyParams::Address {} => serde_json::Value::String(
+                String::from_utf8(Self::unpad(bytes.to_vec()))
+                    .map_err(|_| self.validation_error("invalid utf-8 in index key").unwrap_err())?,
+            ),
+            EntityPropertyParams::Array { .. } | EntityPropertyParams::Object { .. } => {
+                serde_json::from_slice(&Self::unpad(bytes.to_vec()))
+                    .map_err(|_| self.validation_error("invalid json in index key").unwrap_err())?
+            },
+            EntityPropertyParams::U8 {} => u8::from_be_bytes(self.fixed(bytes)?).into(),
+            EntityPropertyParams::U16 {} => u16::from_be_bytes(self.fixed(bytes)?).into(),
+            EntityPropertyParams::U32 {} => u32::from_be_bytes(self.fixed(bytes)?).into(),
+            EntityPropertyParams::U64 {} => u64::from_be_bytes(self.fixed(bytes)?).into(),
+            EntityPropertyParams::U128 {} => u128::from_be_bytes(self.fixed(bytes)?).to_string().into(),
+            EntityPropertyParams::I8 {} => (i8::from_be_bytes(self.fixed(bytes)?) ^ i8::MIN).into(),
+            EntityPropertyParams::I16 {} => (i16::from_be_bytes(self.fixed(bytes)?) ^ i16::MIN).into(),
+            EntityPropertyParams::I32 {} => (i32::from_be_bytes(self.fixed(bytes)?) ^ i32::MIN).into(),
+            EntityPropertyParams::I64 {} => (i64::from_be_bytes(self.fixed(bytes)?) ^ i64::MIN).into(),
+            EntityPropertyParams::I128 {} => (i128::from_be_bytes(self.fixed(bytes)?) ^ i128::MIN).to_string().into(),
+            EntityPropertyParams::Bool {} => (bytes[0] != 0).into(),
+            EntityPropertyParams::Timestamp {} | EntityPropertyParams::Ref { .. } => {
+                u64::from_be_bytes(self.fixed(bytes)?).to_string().into()
+            },
+            EntityPropertyParams::Decimal { scale } => {
+                let atomics = i128::from_be_bytes(self.fixed(bytes)?) ^ i128::MIN;
+                format_decimal(atomics, scale.unwrap_or(DEFAULT_DECIMAL_SCALE)).into()
+            },
+            EntityPropertyParams::Enum { values } => values
+                .get(u16::from_be_bytes(self.fixed(bytes)?) as usize)
+                .ok_or_else(|| self.validation_error("invalid index key").unwrap_err())?
+                .to_owned()
+                .into(),
+            EntityPropertyParams::Binary { .. } => {
+                // Keys come back from cursors too, so don't trust the length
+                let Some(split) = bytes.len().checked_sub(size_of::<u16>()) else {
+                    return Err(self.validation_error("invalid index key").unwrap_err());
+                };
+                let (data, len) = bytes.split_at(split);
+                let len = u16::from_be_bytes(self.fixed(len)?) as usize;
+                if len > data.len() || data[len..].iter().any(|b| *b != 0) {
+                    return Err(self.validation_error("invalid index key").unwrap_err());
+                }
+                Binary::from(&data[..len]).to_base64().into()
+            },
+            EntityPropertyParams::F64 {} => {
+                let bits = u64::from_be_bytes(self.fixed(bytes)?);
+                let mask = if bits >> 63 == 1 { 1 << 63 } else { u64::MAX };
+                f64::from_bits(bits ^ mask).to_string().into()
             },
-            EntityPropertyParams::I64 {} => value.as_i64().unwrap().clamp(0, i64::MAX).to_le_bytes()[..8].to_vec(),
-            EntityPropertyParams::I128 {} => self.pad(value.as_str().unwrap().as_bytes().to_vec())?,
-            EntityPropertyParams::Bool {} => vec![if value.as_bool().unwrap() { 1u8 } else { 0u8 }],
         })
     }
 
+    /// Strip the zero padding added by `pad`.
     pub fn unpad(bytes: Vec<u8>) -> Vec<u8> {
-        let len = bytes.len();
-        let mut i = len - 1;
         let mut bytes = bytes;
-        while i != 0 && bytes[i] == 0 {
+        while bytes.last() == Some(&0) {
             bytes.pop();
-            i -= 1;
         }
         bytes
     }
@@ -95,22 +895,7 @@ impl EntityProperty {
         &self,
         vec: Vec<u8>,
     ) -> Result<Vec<u8>, ContractError> {
-        let target_length = match &self.value {
-            EntityPropertyParams::Array { max_byte_size } => max_byte_size.unwrap_or(DEFAULT_PADDING_ARRAY) as usize,
-            EntityPropertyParams::Object { max_byte_size } => max_byte_size.unwrap_or(DEFAULT_PADDING_OBJECT) as usize,
-            EntityPropertyParams::String { max_byte_size } => max_byte_size.unwrap_or(DEFAULT_PADDING_STRING) as usize,
-            EntityPropertyParams::U8 {} => size_of::<u8>(),
-            EntityPropertyParams::U16 {} => size_of::<u16>(),
-            EntityPropertyParams::U32 {} => size_of::<u32>(),
-            EntityPropertyParams::U64 {} => size_of::<u64>(),
-            EntityPropertyParams::U128 {} => size_of::<u128>(),
-            EntityPropertyParams::I8 {} => size_of::<i8>(),
-            EntityPropertyParams::I16 {} => size_of::<i16>(),
-            EntityPropertyParams::I32 {} => size_of::<i32>(),
-            EntityPropertyParams::I64 {} => size_of::<i64>(),
-            EntityPropertyParams::I128 {} => size_of::<i16>(),
-            EntityPropertyParams::Bool {} => size_of::<bool>(),
-        } as usize;
+        let target_length = self.key_size();
         let n = target_length.saturating_sub(vec.len());
         let padded_vec = {
             if n > 0 {
@@ -130,19 +915,166 @@ impl EntityProperty {
         Ok(padded_vec)
     }
 
+    /// Width in bytes of this property's index keys.
+    pub fn key_size(&self) -> usize {
+        match &self.value {
+            EntityPropertyParams::Array { max_byte_size, .. } => {
+                max_byte_size.unwrap_or(DEFAULT_PADDING_ARRAY) as usize
+            },
+            EntityPropertyParams::Object { max_byte_size, .. } => {
+                max_byte_size.unwrap_or(DEFAULT_PADDING_OBJECT) as usize
+            },
+            EntityPropertyParams::String { max_byte_size } => max_byte_size.unwrap_or(DEFAULT_PADDING_STRING) as usize,
+            EntityPropertyParams::U8 {} => size_of::<u8>(),
+            EntityPropertyParams::U16 {} => size_of::<u16>(),
+            EntityPropertyParams::U32 {} => size_of::<u32>(),
+            EntityPropertyParams::U64 {} => size_of::<u64>(),
+            EntityPropertyParams::U128 {} => size_of::<u128>(),
+            EntityPropertyParams::I8 {} => size_of::<i8>(),
+            EntityPropertyParams::I16 {} => size_of::<i16>(),
+            EntityPropertyParams::I32 {} => size_of::<i32>(),
+            EntityPropertyParams::I64 {} => size_of::<i64>(),
+            EntityPropertyParams::I128 {} => size_of::<i128>(),
+            EntityPropertyParams::Bool {} => size_of::<bool>(),
+            EntityPropertyParams::Address {} => PADDING_ADDRESS as usize,
+            EntityPropertyParams::Timestamp {} | EntityPropertyParams::Ref { .. } => size_of::<u64>(),
+            EntityPropertyParams::Decimal { .. } => size_of::<i128>(),
+            EntityPropertyParams::Enum { .. } => size_of::<u16>(),
+            EntityPropertyParams::Binary { max_byte_size } => {
+                max_byte_size.unwrap_or(DEFAULT_PADDING_BINARY) as usize + size_of::<u16>()
+            },
+            EntityPropertyParams::F64 {} => size_of::<f64>(),
+        }
+    }
+
+    fn fixed<const N: usize>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<[u8; N], ContractError> {
+        bytes
+            .try_into()
+            .map_err(|_| self.validation_error("invalid index key length").unwrap_err())
+    }
+
+    fn expect_str<'v>(
+        &self,
+        value: &'v serde_json::Value,
+    ) -> Result<&'v str, ContractError> {
+        value
+            .as_str()
+            .ok_or_else(|| self.validation_error("expected string").unwrap_err())
+    }
+
+    fn expect_bool(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<bool, ContractError> {
+        value
+            .as_bool()
+            .ok_or_else(|| self.validation_error("expected boolean").unwrap_err())
+    }
+
+    /// Read an integer of type T from a JSON number or, for values that don't
+    /// fit in a JSON number (128-bit integers), from a decimal string.
+    fn expect_int<T: TryFrom<i128>>(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<T, ContractError> {
+        let x = match value {
+            serde_json::Value::String(s) => s.parse::<i128>().ok(),
+            _ => value
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| value.as_u64().map(i128::from)),
+        }
+        .ok_or_else(|| self.validation_error("invalid integer").unwrap_err())?;
+        T::try_from(x).map_err(|_| self.validation_error("out of range").unwrap_err())
+    }
+
+    /// Read a fixed-point decimal, from a string or JSON number, as an
+    /// integer count of units of 10^-scale.
+    fn expect_decimal(
+        &self,
+        value: &serde_json::Value,
+        scale: u8,
+    ) -> Result<i128, ContractError> {
+        let s = match value {
+            serde_json::Value::String(s) => s.to_owned(),
+            serde_json::Value::Number(n) => n.to_string(),
+            _ => return Err(self.validation_error("expected decimal").unwrap_err()),
+        };
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.as_str()),
+        };
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) if !fraction.is_empty() => (whole, fraction),
+            Some(_) => return Err(self.validation_error("invalid decimal").unwrap_err()),
+            None => (digits, ""),
+        };
+        if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(self.validation_error("invalid decimal").unwrap_err());
+        }
+        if fraction.len() > scale as usize {
+            return Err(self.validation_error("too many decimal places").unwrap_err());
+        }
+        let atomics = format!("{}{:0<width$}", whole, fraction, width = scale as usize)
+            .parse::<i128>()
+            .map_err(|_| self.validation_error("out of range").unwrap_err())?;
+        Ok(if negative { -atomics } else { atomics })
+    }
+
+    fn expect_binary(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<Vec<u8>, ContractError> {
+        Binary::from_base64(self.expect_str(value)?)
+            .map(|b| b.to_vec())
+            .map_err(|_| self.validation_error("invalid base64").unwrap_err())
+    }
+
+    fn expect_f64(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<f64, ContractError> {
+        match value {
+            serde_json::Value::String(s) => s.parse::<f64>().ok(),
+            _ => value
+                .as_i64()
+                .map(|x| x as f64)
+                .or_else(|| value.as_u64().map(|x| x as f64)),
+        }
+        .filter(|x| x.is_finite())
+        .ok_or_else(|| self.validation_error("expected number").unwrap_err())
+    }
+
+    fn expect_u128(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<u128, ContractError> {
+        match value {
+            serde_json::Value::String(s) => s.parse::<u128>().ok(),
+            _ => value.as_u64().map(u128::from),
+        }
+        .ok_or_else(|| self.validation_error("invalid u128").unwrap_err())
+    }
+
     pub fn validate(
         &self,
+        api: &dyn Api,
         value: &serde_json::Value,
     ) -> Result<(), ContractError> {
-        match self.value {
+        match &self.value {
             EntityPropertyParams::String { .. } => {
                 self.validate_string(value)?;
             },
             EntityPropertyParams::Object { .. } => {
                 self.validate_object(value)?;
+                self.validate_fields(api, value)?;
             },
             EntityPropertyParams::Array { .. } => {
                 self.validate_array(value)?;
+                self.validate_items(api, value)?;
             },
             EntityPropertyParams::Bool {} => {
                 self.validate_bool(value)?;
@@ -160,7 +1092,7 @@ impl EntityProperty {
                 self.validate_number(value, u64::MIN.into(), u64::MAX.into())?;
             },
             EntityPropertyParams::U128 {} => {
-                self.validate_u128(value, u128::MIN, u128::MAX)?;
+                self.expect_u128(value)?;
             },
             EntityPropertyParams::I8 {} => {
                 self.validate_number(value, i8::MIN.into(), i8::MAX.into())?;
@@ -175,7 +1107,31 @@ impl EntityProperty {
                 self.validate_number(value, i64::MIN.into(), i64::MAX.into())?;
             },
             EntityPropertyParams::I128 {} => {
-                self.validate_number(value, i128::MIN.into(), i128::MAX.into())?;
+                self.expect_int::<i128>(value)?;
+            },
+            EntityPropertyParams::Address {} => {
+                self.validate_string(value)?;
+                if api.addr_validate(self.expect_str(value)?).is_err() {
+                    return self.validation_error("invalid address");
+                }
+            },
+            EntityPropertyParams::Timestamp {} | EntityPropertyParams::Ref { .. } => {
+                self.expect_int::<u64>(value)?;
+            },
+            EntityPropertyParams::Decimal { scale } => {
+                self.expect_decimal(value, scale.unwrap_or(DEFAULT_DECIMAL_SCALE))?;
+            },
+            EntityPropertyParams::Enum { values } => {
+                let s = self.expect_str(value)?;
+                if !values.iter().any(|v| v == s) {
+                    return self.validation_error("not an allowed value");
+                }
+            },
+            EntityPropertyParams::Binary { .. } => {
+                self.to_bytes(value)?;
+            },
+            EntityPropertyParams::F64 {} => {
+                self.expect_f64(value)?;
             },
         }
         Ok(())
@@ -214,6 +1170,69 @@ impl EntityProperty {
         Ok(())
     }
 
+    /// Check the type of each array element, reporting offending ones by
+    /// their position.
+    fn validate_items(
+        &self,
+        api: &dyn Api,
+        value: &serde_json::Value,
+    ) -> Result<(), ContractError> {
+        let (Some(item), Some(elements)) = (self.item_property(), value.as_array()) else {
+            return Ok(());
+        };
+        let mut errors: Vec<String> = vec![];
+        for (i, element) in elements.iter().enumerate() {
+            let item = EntityProperty {
+                name: format!("{}[{}]", self.name, i),
+                ..item.to_owned()
+            };
+            match item.validate(api, element) {
+                Err(ContractError::ValidationError { reason }) => errors.push(reason),
+                Err(e) => return Err(e),
+                Ok(_) => {},
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ContractError::ValidationError {
+                reason: errors.join("; "),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check an object's declared fields, reporting each offending one by its
+    /// path from this property.
+    fn validate_fields(
+        &self,
+        api: &dyn Api,
+        value: &serde_json::Value,
+    ) -> Result<(), ContractError> {
+        let (Some(fields), Some(values)) = (self.fields(), value.as_object()) else {
+            return Ok(());
+        };
+        let mut errors: Vec<String> = vec![];
+        for field in fields {
+            let result = match values.get(&field.name) {
+                Some(value) => field.validate(api, value),
+                None if field.required.unwrap_or(false) => field.validation_error("required"),
+                None => Ok(()),
+            };
+            match result {
+                Err(ContractError::ValidationError { reason }) => {
+                    errors.extend(reason.split("; ").map(|r| format!("{}.{}", self.name, r)))
+                },
+                Err(e) => return Err(e),
+                Ok(_) => {},
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ContractError::ValidationError {
+                reason: errors.join("; "),
+            });
+        }
+        Ok(())
+    }
+
     fn validate_array(
         &self,
         value: &serde_json::Value,
@@ -236,29 +1255,6 @@ impl EntityProperty {
         Ok(())
     }
 
-    fn validate_u128(
-        &self,
-        value: &serde_json::Value,
-        min: u128,
-        max: u128,
-    ) -> Result<(), ContractError> {
-        if let Some(s) = value.as_str() {
-            if let Ok(x) = u128::from_str_radix(s, 10) {
-                if x > max {
-                    return self.validation_error("too large");
-                }
-                if x < min {
-                    return self.validation_error("too small");
-                }
-            } else {
-                return self.validation_error("invalid u128");
-            }
-        } else {
-            return self.validation_error("invalid u128");
-        }
-        Ok(())
-    }
-
     fn validate_number(
         &self,
         value: &serde_json::Value,
@@ -282,3 +1278,135 @@ impl EntityProperty {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn property(value: serde_json::Value) -> EntityProperty {
+        serde_json::from_value(json!({"name": "x", "indexed": true, "value": value})).unwrap()
+    }
+
+    /// Check that values, given in ascending order, encode to ascending keys
+    /// that decode back to the same values.
+    fn assert_ordered_round_trip(
+        value: serde_json::Value,
+        values: &[serde_json::Value],
+    ) {
+        let prop = property(value.to_owned());
+        let keys: Vec<Vec<u8>> = values.iter().map(|v| prop.to_bytes(v).unwrap()).collect();
+        for (key, v) in keys.iter().zip(values) {
+            assert_eq!(key.len(), prop.key_size(), "{} key size of {}", value, v);
+            assert_eq!(&prop.from_bytes(key).unwrap(), v, "{} round trip of {}", value, v);
+        }
+        for (pair, v) in keys.windows(2).zip(values.windows(2)) {
+            assert!(pair[0] < pair[1], "{} order of {} and {}", value, v[0], v[1]);
+        }
+    }
+
+    #[test]
+    fn unsigned_keys_round_trip_in_order() {
+        assert_ordered_round_trip(json!({"u8": {}}), &[json!(0), json!(1), json!(255)]);
+        assert_ordered_round_trip(json!({"u16": {}}), &[json!(0), json!(255), json!(256), json!(u16::MAX)]);
+        assert_ordered_round_trip(json!({"u32": {}}), &[json!(0), json!(255), json!(256), json!(u32::MAX)]);
+        assert_ordered_round_trip(json!({"u64": {}}), &[json!(0), json!(255), json!(256), json!(u64::MAX)]);
+        assert_ordered_round_trip(
+            json!({"u128": {}}),
+            &[
+                json!("0"),
+                json!("256"),
+                json!(u64::MAX.to_string()),
+                json!(u128::MAX.to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn signed_keys_round_trip_in_order() {
+        assert_ordered_round_trip(
+            json!({"i8": {}}),
+            &[json!(i8::MIN), json!(-1), json!(0), json!(1), json!(i8::MAX)],
+        );
+        assert_ordered_round_trip(
+            json!({"i16": {}}),
+            &[
+                json!(i16::MIN),
+                json!(-256),
+                json!(-1),
+                json!(0),
+                json!(256),
+                json!(i16::MAX),
+            ],
+        );
+        assert_ordered_round_trip(
+            json!({"i32": {}}),
+            &[
+                json!(i32::MIN),
+                json!(-256),
+                json!(-1),
+                json!(0),
+                json!(256),
+                json!(i32::MAX),
+            ],
+        );
+        assert_ordered_round_trip(
+            json!({"i64": {}}),
+            &[
+                json!(i64::MIN),
+                json!(-256),
+                json!(-1),
+                json!(0),
+                json!(256),
+                json!(i64::MAX),
+            ],
+        );
+        assert_ordered_round_trip(
+            json!({"i128": {}}),
+            &[
+                json!(i128::MIN.to_string()),
+                json!(i64::MIN.to_string()),
+                json!("-1"),
+                json!("0"),
+                json!("1"),
+                json!(i128::MAX.to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn other_numeric_keys_round_trip_in_order() {
+        assert_ordered_round_trip(
+            json!({"timestamp": {}}),
+            &[json!("0"), json!("1000"), json!(u64::MAX.to_string())],
+        );
+        assert_ordered_round_trip(
+            json!({"decimal": {"scale": 2}}),
+            &[
+                json!("-12.50"),
+                json!("-0.01"),
+                json!("0.00"),
+                json!("0.01"),
+                json!("12.50"),
+            ],
+        );
+        let prop = property(json!({"f64": {}}));
+        let values = ["-1e300", "-1.5", "-0", "0", "1e-300", "1.5", "1e300"];
+        let keys: Vec<Vec<u8>> = values.iter().map(|v| prop.to_bytes(&json!(v)).unwrap()).collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        for key in keys {
+            assert_eq!(prop.to_bytes(&prop.from_bytes(&key).unwrap()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        let prop = property(json!({"i32": {}}));
+        assert!(prop.to_bytes(&json!(i64::from(i32::MAX) + 1)).is_err());
+        assert!(prop.from_bytes(&[0, 0]).is_err());
+        assert!(property(json!({"u8": {}})).to_bytes(&json!(-1)).is_err());
+        let prop = property(json!({"binary": {}}));
+        assert!(prop.from_bytes(&[0]).is_err());
+        assert!(prop.from_bytes(&[1, 2, 0, 9]).is_err());
+    }
+}
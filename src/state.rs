@@ -1,24 +1,68 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, Timestamp, Uint64};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, Storage, Timestamp, Uint64};
+use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::Expiration;
 use serde_json;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 
 use crate::{
     error::ContractError,
-    msg::{CreateArgs, DeleteArgs, InstantiateMsg, UpdateArgs, UpdateMode},
-    schema::{EntityProperty, EntitySchema},
+    msg::{CreateArgs, DeleteArgs, InstantiateMsg, SchemaOperation, TransferOwnershipArgs, UpdateArgs, UpdateMode},
+    query::read::read_remote,
+    schema::{lookup, lookup_value, remove_path, EntityProperty, EntityPropertyParams, EntitySchema, RefPolicy},
 };
 
 pub type ObjectId = u64;
 pub type PropertyIndex<'a> = Map<'a, (&'a [u8], ObjectId), u8>;
+pub type UniqueIndex<'a> = Map<'a, &'a [u8], ObjectId>;
 
 pub const OPERATOR: Item<Addr> = Item::new("op");
+pub const PENDING_OPERATOR: Item<OperatorProposal> = Item::new("op_pending");
+pub const ROLES: Map<&Addr, RoleGrant> = Map::new("roles");
 pub const METADATA: Item<CollectionMetadata> = Item::new("meta");
 pub const SCHEMA: Item<EntitySchema> = Item::new("schema");
 pub const CREATED_AT: Map<ObjectId, Timestamp> = Map::new("tc");
 pub const UPDATED_AT: Map<ObjectId, Timestamp> = Map::new("tu");
+pub const CREATED_HEIGHT: Map<ObjectId, u64> = Map::new("hc");
+pub const UPDATED_HEIGHT: Map<ObjectId, u64> = Map::new("hu");
+pub const REVISION: Map<ObjectId, u32> = Map::new("rev");
+pub const HISTORY: Map<(ObjectId, u32), EntityRevision> = Map::new("history");
+pub const TRASH: Map<ObjectId, TrashedEntity> = Map::new("trash");
+pub const TRASH_BY_TIME: Map<(u64, ObjectId), u8> = Map::new("trash_t");
+pub const VALIDITY: Map<ObjectId, Validity> = Map::new("validity");
+pub const EXPIRY: Map<(u64, ObjectId), u8> = Map::new("expiry");
 pub const ENTITY: Map<ObjectId, serde_json::Value> = Map::new("entities");
+pub const OWNER: Map<ObjectId, Addr> = Map::new("owner");
+pub const OWNED: Map<(&Addr, ObjectId), u8> = Map::new("owned");
+pub const CONFIG: Item<CollectionConfig> = Item::new("config");
 pub const COUNT: Item<u32> = Item::new("n");
+pub const ID_SEQ: Item<ObjectId> = Item::new("id_seq");
+pub const INDEX_ENCODING: Item<u8> = Item::new("ix_enc");
+pub const JOB: Item<Job> = Item::new("job");
+pub const JOB_SEQ: Item<u32> = Item::new("job_seq");
+/// Entities ahead of the job cursor that were already written against the
+/// current schema, keyed to the job's sequence number.
+pub const MIGRATED: Map<ObjectId, u32> = Map::new("migrated");
+
+pub const DEFAULT_HISTORY_DEPTH: u32 = 10;
+pub const DEFAULT_TRASH_RETENTION: u64 = 30 * 24 * 60 * 60;
+pub const DEFAULT_JOB_BATCH_SIZE: u32 = 50;
+pub const MAX_JOB_BATCH_SIZE: u32 = 500;
+/// Most entities a delete cascades to, or clears references from, through
+/// each reference property
+pub const MAX_REFERRERS: usize = 30;
+
+/// Version of the index key encoding produced by `EntityProperty::to_bytes`.
+/// Collections whose stored version is older have their indexes rebuilt on
+/// migrate. Version 0 (the implicit default) is the little-endian encoding.
+/// Version 2 adds the timestamp indexes.
+pub const INDEX_ENCODING_VERSION: u8 = 2;
+
+/// Pseudo-properties holding an entity's creation and last write time, which
+/// can be read by range like an indexed U64 of nanoseconds.
+pub const CREATED_AT_PROPERTY: &str = "_created_at";
+pub const UPDATED_AT_PROPERTY: &str = "_updated_at";
 
 #[cw_serde]
 pub struct CollectionMetadata {
@@ -27,6 +71,252 @@ pub struct CollectionMetadata {
     pub website: Option<String>,
 }
 
+#[cw_serde]
+#[derive(Default)]
+pub struct CollectionConfig {
+    /// Let any address create entities that it owns
+    pub public_create: Option<bool>,
+    /// How IDs are assigned to new entities, caller-supplied by default
+    pub id_strategy: Option<IdStrategy>,
+    /// How many previous revisions of each entity to keep, 10 by default
+    pub history_depth: Option<u32>,
+    /// Move deleted entities to the trash, from which they can be restored
+    pub soft_delete: Option<bool>,
+    /// Seconds before trashed entities can be purged, 30 days by default
+    pub trash_retention: Option<u64>,
+}
+
+/// The window in which an entity is active, from `valid_from` inclusive up to
+/// `expires_at` exclusive. Either end may be left open.
+#[cw_serde]
+#[derive(Default)]
+pub struct Validity {
+    pub valid_from: Option<Timestamp>,
+    pub expires_at: Option<Timestamp>,
+}
+
+impl Validity {
+    pub fn contains(
+        &self,
+        time: Timestamp,
+    ) -> bool {
+        self.valid_from.is_none_or(|t| t <= time) && self.expires_at.is_none_or(|t| time < t)
+    }
+}
+
+/// A soft-deleted entity's data, kept out of `ENTITY` so that reads and
+/// writes no longer see it.
+#[cw_serde]
+pub struct TrashedEntity {
+    pub data: serde_json::Value,
+    pub deleted_at: Timestamp,
+}
+
+/// A previous version of an entity and when it was written.
+#[cw_serde]
+pub struct EntityRevision {
+    pub data: serde_json::Value,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub enum IdStrategy {
+    /// The caller supplies the ID in `CreateArgs`
+    Caller,
+    /// IDs are assigned from a counter, starting at 1
+    Sequence,
+    /// The ID is derived from a SHA-256 hash of the validated entity data
+    ContentHash,
+}
+
+#[cw_serde]
+pub struct OperatorProposal {
+    pub operator: Addr,
+    pub proposer: Addr,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub enum Role {
+    /// Every write action, plus granting and revoking non-admin roles
+    Admin,
+    /// Create entities
+    Writer,
+    /// Update entities
+    Updater,
+    /// Delete entities
+    Deleter,
+}
+
+#[cw_serde]
+pub struct RoleGrant {
+    pub roles: Vec<Role>,
+    pub expires: Expiration,
+    /// Number of write actions remaining, if limited
+    pub quota: Option<u32>,
+}
+
+impl RoleGrant {
+    pub fn allows(
+        &self,
+        role: &Role,
+        block: &BlockInfo,
+    ) -> bool {
+        !self.expires.is_expired(block)
+            && self.quota != Some(0)
+            && self.roles.iter().any(|r| r == role || *r == Role::Admin)
+    }
+}
+
+/// Background work over the whole collection, run in bounded batches by
+/// `ContinueJob`. Indices listed in `clear_indices` are emptied first, then
+/// the steps are applied to each entity in ID order. Writes to an entity the
+/// cursor hasn't reached yet apply the steps to it first and mark it as
+/// migrated, so the job passes over it later. Entities whose stored values
+/// can't be indexed are left out of the index and counted as skipped.
+#[cw_serde]
+pub struct Job {
+    #[serde(default)]
+    pub seq: u32,
+    pub clear_indices: Vec<String>,
+    pub steps: Vec<JobStep>,
+    pub cursor: Option<ObjectId>,
+    pub processed: u32,
+    pub total: u32,
+    #[serde(default)]
+    pub skipped: u32,
+}
+
+impl Job {
+    /// Indices that can't be relied upon until the job completes, by their
+    /// storage name, such as `_ix_price` or `_ux_email`.
+    pub fn incomplete_indices(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.clear_indices.iter().map(|name| format!("_ix_{}", name)).collect();
+        for step in self.steps.iter() {
+            match step {
+                JobStep::BuildIndex { property } => names.push(format!("_ix_{}", property)),
+                JobStep::BuildSearchIndex { property } => names.push(format!("_tx_{}", property)),
+                JobStep::RenameProperty { new_name, .. } => {
+                    names.extend(["_ix_", "_ux_", "_tx_", "_rx_"].map(|kind| format!("{}{}", kind, new_name)))
+                },
+                // Indexed fields of a default get their own build steps
+                JobStep::SetDefault { .. }
+                | JobStep::RemoveProperty { .. }
+                | JobStep::DropIndex { .. }
+                | JobStep::DropSearchIndex { .. } => {},
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+fn require_not_compound_indexed(
+    schema: &EntitySchema,
+    property: &str,
+) -> Result<(), ContractError> {
+    let nested = format!("{}.", property);
+    if let Some(index) = schema
+        .indexes
+        .iter()
+        .flatten()
+        .find(|ix| ix.properties.iter().any(|p| p == property || p.starts_with(&nested)))
+    {
+        return Err(ContractError::ValidationError {
+            reason: format!("property {} is used by index {}", property, index.name),
+        });
+    }
+    Ok(())
+}
+
+/// Properties are only dropped or renamed as a whole, so they must be at the
+/// top level and have no indexed, unique, searchable or reference nested
+/// fields.
+fn require_top_level(
+    schema: &EntitySchema,
+    property: &str,
+) -> Result<(), ContractError> {
+    if !schema.properties.iter().any(|p| p.name == property) {
+        return Err(ContractError::ValidationError {
+            reason: format!("{} - not a top-level property", property),
+        });
+    }
+    let nested = format!("{}.", property);
+    if let Some((path, _)) = schema.paths().into_iter().find(|(path, p)| {
+        path.starts_with(&nested)
+            && (p.indexed.unwrap_or(false)
+                || p.unique.unwrap_or(false)
+                || p.searchable.unwrap_or(false)
+                || matches!(p.value, EntityPropertyParams::Ref { .. }))
+    }) {
+        return Err(ContractError::ValidationError {
+            reason: format!("property {} has indexed field {}", property, path),
+        });
+    }
+    require_not_compound_indexed(schema, property)
+}
+
+/// Queue a job, failing if another one is still in progress.
+pub fn start_job(
+    storage: &mut dyn Storage,
+    clear_indices: Vec<String>,
+    steps: Vec<JobStep>,
+) -> Result<(), ContractError> {
+    if JOB.exists(storage) {
+        return Err(ContractError::NotAuthorized {
+            reason: "another job is still in progress".to_owned(),
+        });
+    }
+    let total = COUNT.load(storage)?;
+    let seq = JOB_SEQ.may_load(storage)?.unwrap_or_default().wrapping_add(1);
+    JOB_SEQ.save(storage, &seq)?;
+    JOB.save(
+        storage,
+        &Job {
+            seq,
+            clear_indices,
+            steps,
+            cursor: None,
+            processed: 0,
+            total,
+            skipped: 0,
+        },
+    )?;
+    Ok(())
+}
+
+#[cw_serde]
+pub enum JobStep {
+    /// Fill in the property's schema default where missing
+    SetDefault {
+        property: String,
+    },
+    /// Remove a property and its index entry, given its prior definition
+    RemoveProperty {
+        property: EntityProperty,
+    },
+    /// Move a property to a new name, given its prior definition
+    RenameProperty {
+        property: EntityProperty,
+        new_name: String,
+    },
+    BuildIndex {
+        property: String,
+    },
+    /// Remove a property's index entry, given its prior definition
+    DropIndex {
+        property: EntityProperty,
+    },
+    BuildSearchIndex {
+        property: String,
+    },
+    /// Remove a property's token index entries, given its prior definition
+    DropSearchIndex {
+        property: EntityProperty,
+    },
+}
+
 pub struct ExecuteContext<'a> {
     pub deps: DepsMut<'a>,
     pub env: Env,
@@ -55,13 +345,17 @@ impl<'a> ExecuteContext<'a> {
             operator,
             schema,
             metadata,
+            config,
         } = msg;
 
+        schema.validate_indexes(self.deps.api)?;
         COUNT.save(self.deps.storage, &0)?;
+        INDEX_ENCODING.save(self.deps.storage, &INDEX_ENCODING_VERSION)?;
         SCHEMA.save(self.deps.storage, &schema)?;
+        CONFIG.save(self.deps.storage, &config.unwrap_or_default())?;
         METADATA.save(
             self.deps.storage,
-            &metadata.unwrap_or_else(|| CollectionMetadata {
+            &metadata.unwrap_or(CollectionMetadata {
                 name: None,
                 description: None,
                 website: None,
@@ -102,6 +396,72 @@ impl<'a> ExecuteContext<'a> {
         Ok(())
     }
 
+    /// First step of an operator handover. Replaces any existing proposal.
+    pub fn propose_operator(
+        &mut self,
+        new_operator: &Addr,
+        expires: Option<Expiration>,
+    ) -> Result<(), ContractError> {
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&self.env.block) {
+            return Err(ContractError::ValidationError {
+                reason: "proposal already expired".to_owned(),
+            });
+        }
+        let operator =
+            self.deps
+                .api
+                .addr_validate(new_operator.as_str())
+                .map_err(|_| ContractError::ValidationError {
+                    reason: "invalid new operator address".to_owned(),
+                })?;
+        PENDING_OPERATOR.save(
+            self.deps.storage,
+            &OperatorProposal {
+                operator,
+                proposer: self.info.sender.to_owned(),
+                expires,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Second step of an operator handover, performed by the proposed operator.
+    pub fn accept_operator(&mut self) -> Result<(), ContractError> {
+        let proposal = PENDING_OPERATOR
+            .may_load(self.deps.storage)?
+            .ok_or_else(|| ContractError::NotFound {
+                reason: "no pending operator proposal".to_owned(),
+            })?;
+        if proposal.operator != self.info.sender {
+            return Err(ContractError::NotAuthorized {
+                reason: "proposed operator required".to_owned(),
+            });
+        }
+        if proposal.expires.is_expired(&self.env.block) {
+            return Err(ContractError::NotAuthorized {
+                reason: "operator proposal expired".to_owned(),
+            });
+        }
+        PENDING_OPERATOR.remove(self.deps.storage);
+        self.set_operator(&proposal.operator)
+    }
+
+    /// Withdraw a pending proposal. Either the operator or the proposed
+    /// operator may cancel it.
+    pub fn cancel_operator_proposal(&mut self) -> Result<(), ContractError> {
+        let proposal = PENDING_OPERATOR
+            .may_load(self.deps.storage)?
+            .ok_or_else(|| ContractError::NotFound {
+                reason: "no pending operator proposal".to_owned(),
+            })?;
+        if proposal.operator != self.info.sender {
+            self.require_operator()?;
+        }
+        PENDING_OPERATOR.remove(self.deps.storage);
+        Ok(())
+    }
+
     pub fn require_operator(&self) -> Result<(), ContractError> {
         if self.info.sender != OPERATOR.load(self.deps.storage)? {
             return Err(ContractError::NotAuthorized {
@@ -111,19 +471,197 @@ impl<'a> ExecuteContext<'a> {
         Ok(())
     }
 
-    pub fn create_entity(
+    /// Require the sender to be the operator or to hold the given role,
+    /// consuming one unit of the sender's write quota if it has one.
+    pub fn require_role(
         &mut self,
-        args: CreateArgs,
+        role: Role,
     ) -> Result<(), ContractError> {
-        let CreateArgs { id, data } = args;
-        let id = id.u64();
-        if ENTITY.has(self.deps.storage, id) {
+        if self.info.sender == OPERATOR.load(self.deps.storage)? {
+            return Ok(());
+        }
+        let sender = &self.info.sender;
+        match ROLES.may_load(self.deps.storage, sender)? {
+            Some(mut grant) if grant.allows(&role, &self.env.block) => {
+                if let Some(n) = grant.quota {
+                    grant.quota = Some(n - 1);
+                    ROLES.save(self.deps.storage, sender, &grant)?;
+                }
+                Ok(())
+            },
+            _ => Err(ContractError::NotAuthorized {
+                reason: format!("{:?} role required", role),
+            }),
+        }
+    }
+
+    /// Require the sender to own the given entity or else hold the role.
+    pub fn require_owner_or_role(
+        &mut self,
+        id: ObjectId,
+        role: Role,
+    ) -> Result<(), ContractError> {
+        if OWNER.may_load(self.deps.storage, id)? == Some(self.info.sender.to_owned()) {
+            return Ok(());
+        }
+        self.require_role(role)
+    }
+
+    /// Authorize the sender to create an entity with the given owner,
+    /// returning the owner to record. Writers may assign any owner or none.
+    /// Otherwise, if the collection allows public creation, the sender
+    /// becomes the owner.
+    pub fn authorize_create(
+        &mut self,
+        owner: Option<Addr>,
+    ) -> Result<Option<Addr>, ContractError> {
+        let public_create = CONFIG
+            .may_load(self.deps.storage)?
+            .unwrap_or_default()
+            .public_create
+            .unwrap_or(false);
+        let is_writer = self.require_role(Role::Writer);
+        if is_writer.is_ok() {
+            return Ok(owner);
+        }
+        if !public_create || owner.as_ref().map(|o| *o != self.info.sender).unwrap_or(false) {
+            return is_writer.map(|_| owner);
+        }
+        Ok(Some(self.info.sender.to_owned()))
+    }
+
+    /// Set or clear the owner of an entity, maintaining the owner index.
+    pub fn set_owner(
+        &mut self,
+        id: ObjectId,
+        owner: Option<&Addr>,
+    ) -> Result<(), ContractError> {
+        if let Some(prev_owner) = OWNER.may_load(self.deps.storage, id)? {
+            OWNED.remove(self.deps.storage, (&prev_owner, id));
+        }
+        if let Some(owner) = owner {
+            let owner = self.deps.api.addr_validate(owner.as_str())?;
+            OWNER.save(self.deps.storage, id, &owner)?;
+            OWNED.save(self.deps.storage, (&owner, id), &1)?;
+        } else {
+            OWNER.remove(self.deps.storage, id);
+        }
+        Ok(())
+    }
+
+    /// Admins may manage the roles of other addresses, except Admin itself
+    /// and grants holding it, which only the operator can change.
+    fn require_role_manager(
+        &self,
+        address: &Addr,
+        roles: &[Role],
+    ) -> Result<(), ContractError> {
+        if self.require_operator().is_ok() {
+            return Ok(());
+        }
+        let is_admin = ROLES
+            .may_load(self.deps.storage, &self.info.sender)?
+            .map(|grant| !grant.expires.is_expired(&self.env.block) && grant.roles.contains(&Role::Admin))
+            .unwrap_or(false);
+        if !is_admin || roles.contains(&Role::Admin) {
+            return Err(ContractError::NotAuthorized {
+                reason: "operator or admin required".to_owned(),
+            });
+        }
+        if *address == self.info.sender {
             return Err(ContractError::NotAuthorized {
+                reason: "admins cannot change their own roles".to_owned(),
+            });
+        }
+        let target_is_admin = ROLES
+            .may_load(self.deps.storage, address)?
+            .map(|grant| grant.roles.contains(&Role::Admin))
+            .unwrap_or(false);
+        if target_is_admin {
+            return Err(ContractError::NotAuthorized {
+                reason: format!("only the operator can change the roles of admin {}", address),
+            });
+        }
+        Ok(())
+    }
+
+    /// Add roles to an address, replacing the expiry and quota of any
+    /// existing grant.
+    pub fn grant_roles(
+        &mut self,
+        address: &Addr,
+        roles: Vec<Role>,
+        expires: Option<Expiration>,
+        quota: Option<u32>,
+    ) -> Result<(), ContractError> {
+        let address = self.deps.api.addr_validate(address.as_str())?;
+        self.require_role_manager(&address, &roles)?;
+        let mut grant = ROLES.may_load(self.deps.storage, &address)?.unwrap_or(RoleGrant {
+            roles: vec![],
+            expires: Expiration::Never {},
+            quota: None,
+        });
+        for role in roles {
+            if !grant.roles.contains(&role) {
+                grant.roles.push(role);
+            }
+        }
+        grant.expires = expires.unwrap_or_default();
+        grant.quota = quota;
+        ROLES.save(self.deps.storage, &address, &grant)?;
+        Ok(())
+    }
+
+    /// Remove the given roles from an address, or all of them if none given.
+    pub fn revoke_roles(
+        &mut self,
+        address: &Addr,
+        roles: Option<Vec<Role>>,
+    ) -> Result<(), ContractError> {
+        let mut grant = ROLES
+            .may_load(self.deps.storage, address)?
+            .ok_or_else(|| ContractError::NotFound {
+                reason: format!("no roles granted to {}", address),
+            })?;
+        let roles = roles.unwrap_or_else(|| grant.roles.to_owned());
+        self.require_role_manager(address, &roles)?;
+        grant.roles.retain(|r| !roles.contains(r));
+        if grant.roles.is_empty() {
+            ROLES.remove(self.deps.storage, address);
+        } else {
+            ROLES.save(self.deps.storage, address, &grant)?;
+        }
+        Ok(())
+    }
+
+    /// Create an entity, returning the ID assigned to it.
+    pub fn create_entity(
+        &mut self,
+        args: CreateArgs,
+    ) -> Result<ObjectId, ContractError> {
+        let CreateArgs {
+            id,
+            data,
+            owner,
+            validity,
+        } = args;
+        let schema = self.load_schema()?;
+        let data = schema.validate(self.deps.api, &data)?;
+        let id = self.allocate_id(id, &data)?;
+        if ENTITY.has(self.deps.storage, id) || TRASH.has(self.deps.storage, id) {
+            return Err(ContractError::AlreadyExists {
                 reason: format!("entity {} already exists", id),
             });
         }
+        self.require_unique(id, &data, &schema)?;
+        self.require_refs(id, &serde_json::Value::Object(serde_json::Map::new()), &data, &schema)?;
         ENTITY.save(self.deps.storage, id, &data)?;
-        CREATED_AT.save(self.deps.storage, id, &self.env.block.time)?;
+        self.mark_migrated(id)?;
+        self.stamp(id, true)?;
+        self.set_owner(id, owner.as_ref())?;
+        if let Some(validity) = validity {
+            self.set_validity(id, validity)?;
+        }
         COUNT.update(self.deps.storage, |x| -> Result<_, ContractError> {
             x.checked_add(1).ok_or_else(|| ContractError::Unexpected {
                 reason: "collection max size reached".to_owned(),
@@ -133,12 +671,92 @@ impl<'a> ExecuteContext<'a> {
             &id.into(),
             &serde_json::Value::Object(serde_json::Map::new()),
             &data,
-            &self.load_schema()?,
-            None,
+            &schema,
         )?;
+        Ok(id)
+    }
+
+    /// Record the current block as an entity's last write, and as its creation
+    /// too if `created`, keeping the timestamp indexes in step.
+    fn stamp(
+        &mut self,
+        id: ObjectId,
+        created: bool,
+    ) -> Result<(), ContractError> {
+        let names: &[&str] = if created {
+            &[CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY]
+        } else {
+            &[UPDATED_AT_PROPERTY]
+        };
+        self.unindex_timestamps(id, names)?;
+        let BlockInfo { time, height, .. } = self.env.block;
+        if created {
+            CREATED_AT.save(self.deps.storage, id, &time)?;
+            CREATED_HEIGHT.save(self.deps.storage, id, &height)?;
+        }
+        UPDATED_AT.save(self.deps.storage, id, &time)?;
+        UPDATED_HEIGHT.save(self.deps.storage, id, &height)?;
+        self.index_timestamps(id, names)
+    }
+
+    fn index_timestamps(
+        &mut self,
+        id: ObjectId,
+        names: &[&str],
+    ) -> Result<(), ContractError> {
+        for name in names {
+            if let Some(time) = load_timestamp(self.deps.storage, name, id)? {
+                let index_name = format!("_ix_{}", name);
+                PropertyIndex::new(&index_name).save(self.deps.storage, (&time.nanos().to_be_bytes(), id), &1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn unindex_timestamps(
+        &mut self,
+        id: ObjectId,
+        names: &[&str],
+    ) -> Result<(), ContractError> {
+        for name in names {
+            if let Some(time) = load_timestamp(self.deps.storage, name, id)? {
+                let index_name = format!("_ix_{}", name);
+                PropertyIndex::new(&index_name).remove(self.deps.storage, (&time.nanos().to_be_bytes(), id));
+            }
+        }
         Ok(())
     }
 
+    fn allocate_id(
+        &mut self,
+        id: Option<Uint64>,
+        data: &serde_json::Value,
+    ) -> Result<ObjectId, ContractError> {
+        let strategy = CONFIG
+            .may_load(self.deps.storage)?
+            .unwrap_or_default()
+            .id_strategy
+            .unwrap_or(IdStrategy::Caller);
+        match (strategy, id) {
+            (IdStrategy::Caller, Some(id)) => Ok(id.u64()),
+            (IdStrategy::Caller, None) => Err(ContractError::ValidationError {
+                reason: "id required".to_owned(),
+            }),
+            (_, Some(_)) => Err(ContractError::ValidationError {
+                reason: "id is assigned by the collection".to_owned(),
+            }),
+            (IdStrategy::Sequence, None) => {
+                let id = ID_SEQ.may_load(self.deps.storage)?.unwrap_or_default() + 1;
+                ID_SEQ.save(self.deps.storage, &id)?;
+                Ok(id)
+            },
+            (IdStrategy::ContentHash, None) => {
+                let digest = Sha256::digest(data.to_string().as_bytes());
+                Ok(u64::from_be_bytes(digest[..8].try_into().unwrap()))
+            },
+        }
+    }
+
     pub fn update_entity(
         &mut self,
         args: UpdateArgs,
@@ -147,26 +765,37 @@ impl<'a> ExecuteContext<'a> {
             id,
             data: new_data,
             mode,
+            expected_revision,
+            validity,
         } = args;
-        if let Ok(mut curr_data) = ENTITY.load(self.deps.storage, id.u64()) {
+        if let Ok(curr_data) = ENTITY.load(self.deps.storage, id.u64()) {
+            self.require_revision(id.u64(), expected_revision)?;
             let schema = self.load_schema()?;
-            self.update_indices(&id, &curr_data, &new_data, &schema, Some(mode.to_owned()))?;
-            UPDATED_AT.save(self.deps.storage, id.u64(), &self.env.block.time)?;
-            match mode {
+            let curr_data = self.apply_pending_job(id.u64(), curr_data, &schema)?;
+            let new_data = match mode {
                 UpdateMode::Merge => {
-                    let curr_obj = curr_data.as_object_mut().unwrap();
-                    for prop in schema.properties.iter() {
-                        if let Some(v) = new_data.get(&prop.name) {
-                            prop.validate(&v)?;
-                            curr_obj.insert(prop.name.to_owned(), v.to_owned());
-                        }
-                    }
-                    curr_obj.extend(new_data.as_object().unwrap().clone());
-                    ENTITY.save(self.deps.storage, id.u64(), &curr_data)?;
+                    let mut merged = curr_data.to_owned();
+                    let merged_obj = merged.as_object_mut().ok_or_else(|| ContractError::Unexpected {
+                        reason: "entity data not an object".to_owned(),
+                    })?;
+                    let new_obj = new_data.as_object().ok_or_else(|| ContractError::ValidationError {
+                        reason: "entity is not an object".to_owned(),
+                    })?;
+                    merged_obj.extend(new_obj.to_owned());
+                    schema.validate(self.deps.api, &merged)?
                 },
-                UpdateMode::Replace => ENTITY.save(self.deps.storage, id.u64(), &new_data)?,
+                UpdateMode::Replace => schema.validate(self.deps.api, &new_data)?,
             };
-            Ok(())
+            self.require_unique(id.u64(), &new_data, &schema)?;
+            self.require_refs(id.u64(), &curr_data, &new_data, &schema)?;
+            self.update_indices(&id, &curr_data, &new_data, &schema)?;
+            self.save_revision(id.u64(), curr_data)?;
+            self.stamp(id.u64(), false)?;
+            if let Some(validity) = validity {
+                self.set_validity(id.u64(), validity)?;
+            }
+            ENTITY.save(self.deps.storage, id.u64(), &new_data)?;
+            self.mark_migrated(id.u64())
         } else {
             Err(ContractError::NotFound {
                 reason: format!("entity {} not found", id.u64()),
@@ -178,19 +807,37 @@ impl<'a> ExecuteContext<'a> {
         &mut self,
         args: DeleteArgs,
     ) -> Result<(), ContractError> {
-        let DeleteArgs { id } = args;
+        let DeleteArgs { id, expected_revision } = args;
         if let Ok(data) = ENTITY.load(self.deps.storage, id.u64()) {
+            self.require_revision(id.u64(), expected_revision)?;
             let schema = self.load_schema()?;
+            let data = self.apply_pending_job(id.u64(), data, &schema)?;
+            self.require_unreferenced(id.u64(), &schema)?;
             self.remove_entity_from_indices(id.u64(), &schema, &data)?;
+            self.unindex_timestamps(id.u64(), &[CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY])?;
+            self.unindex_expiry(id.u64())?;
             ENTITY.remove(self.deps.storage, id.u64());
-            UPDATED_AT.remove(self.deps.storage, id.u64());
-            CREATED_AT.remove(self.deps.storage, id.u64());
+            MIGRATED.remove(self.deps.storage, id.u64());
             COUNT.update(self.deps.storage, |x| -> Result<_, ContractError> {
                 x.checked_sub(1).ok_or_else(|| ContractError::Unexpected {
                     reason: "collection count already zero".to_owned(),
                 })
             })?;
-            Ok(())
+
+            let config = CONFIG.may_load(self.deps.storage)?.unwrap_or_default();
+            if config.soft_delete.unwrap_or(false) {
+                // Keep the owner, timestamps and history for a restore, but
+                // leave the entity out of the owner's listing
+                if let Some(owner) = OWNER.may_load(self.deps.storage, id.u64())? {
+                    OWNED.remove(self.deps.storage, (&owner, id.u64()));
+                }
+                let deleted_at = self.env.block.time;
+                TRASH.save(self.deps.storage, id.u64(), &TrashedEntity { data, deleted_at })?;
+                TRASH_BY_TIME.save(self.deps.storage, (deleted_at.nanos(), id.u64()), &1)?;
+            } else {
+                self.erase_entity(id.u64())?;
+            }
+            self.apply_delete_policies(id.u64(), &schema)
         } else {
             Err(ContractError::NotFound {
                 reason: format!("entity {} not found", id.u64()),
@@ -198,6 +845,664 @@ impl<'a> ExecuteContext<'a> {
         }
     }
 
+    /// Bring a soft-deleted entity back out of the trash. Its data is checked
+    /// against the current schema, which may have changed since it was
+    /// deleted.
+    pub fn restore_entity(
+        &mut self,
+        id: Uint64,
+    ) -> Result<(), ContractError> {
+        let TrashedEntity { data, deleted_at } =
+            TRASH
+                .may_load(self.deps.storage, id.u64())?
+                .ok_or_else(|| ContractError::NotFound {
+                    reason: format!("entity {} not in trash", id),
+                })?;
+        let schema = self.load_schema()?;
+        let data = schema.validate(self.deps.api, &data)?;
+        self.require_unique(id.u64(), &data, &schema)?;
+        self.require_refs(
+            id.u64(),
+            &serde_json::Value::Object(serde_json::Map::new()),
+            &data,
+            &schema,
+        )?;
+
+        TRASH.remove(self.deps.storage, id.u64());
+        TRASH_BY_TIME.remove(self.deps.storage, (deleted_at.nanos(), id.u64()));
+        ENTITY.save(self.deps.storage, id.u64(), &data)?;
+        self.mark_migrated(id.u64())?;
+        if let Some(owner) = OWNER.may_load(self.deps.storage, id.u64())? {
+            OWNED.save(self.deps.storage, (&owner, id.u64()), &1)?;
+        }
+        self.index_timestamps(id.u64(), &[CREATED_AT_PROPERTY])?;
+        self.stamp(id.u64(), false)?;
+        if let Some(validity) = VALIDITY.may_load(self.deps.storage, id.u64())? {
+            self.set_validity(id.u64(), validity)?;
+        }
+        COUNT.update(self.deps.storage, |x| -> Result<_, ContractError> {
+            x.checked_add(1).ok_or_else(|| ContractError::Unexpected {
+                reason: "collection max size reached".to_owned(),
+            })
+        })?;
+        self.update_indices(&id, &serde_json::Value::Object(serde_json::Map::new()), &data, &schema)
+    }
+
+    /// Permanently remove up to `limit` entities that have been in the trash
+    /// for longer than the retention window. Returns how many were removed,
+    /// and whether no expired ones remain.
+    pub fn purge_trash(
+        &mut self,
+        limit: Option<u32>,
+    ) -> Result<(u32, bool), ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_JOB_BATCH_SIZE).clamp(1, MAX_JOB_BATCH_SIZE) as usize;
+        let retention = CONFIG
+            .may_load(self.deps.storage)?
+            .unwrap_or_default()
+            .trash_retention
+            .unwrap_or(DEFAULT_TRASH_RETENTION);
+        // A retention window reaching back past the epoch has nothing to purge
+        let Some(cutoff) = retention
+            .checked_mul(1_000_000_000)
+            .and_then(|retention| self.env.block.time.nanos().checked_sub(retention))
+        else {
+            return Ok((0, true));
+        };
+        let expired = TRASH_BY_TIME
+            .keys(
+                self.deps.storage,
+                None,
+                Some(Bound::exclusive((cutoff, ObjectId::MIN))),
+                Order::Ascending,
+            )
+            .take(limit + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+        let done = expired.len() <= limit;
+
+        let mut n_purged = 0;
+        for (time, id) in expired.into_iter().take(limit) {
+            TRASH_BY_TIME.remove(self.deps.storage, (time, id));
+            TRASH.remove(self.deps.storage, id);
+            self.erase_entity(id)?;
+            n_purged += 1;
+        }
+        Ok((n_purged, done))
+    }
+
+    /// Remove everything kept about an entity besides its data and indexes.
+    fn erase_entity(
+        &mut self,
+        id: ObjectId,
+    ) -> Result<(), ContractError> {
+        self.set_owner(id, None)?;
+        for map in [CREATED_AT, UPDATED_AT] {
+            map.remove(self.deps.storage, id);
+        }
+        for map in [CREATED_HEIGHT, UPDATED_HEIGHT] {
+            map.remove(self.deps.storage, id);
+        }
+        let revisions = HISTORY
+            .prefix(id)
+            .keys(self.deps.storage, None, None, Order::Ascending)
+            .collect::<Result<Vec<_>, _>>()?;
+        for revision in revisions {
+            HISTORY.remove(self.deps.storage, (id, revision));
+        }
+        REVISION.remove(self.deps.storage, id);
+        VALIDITY.remove(self.deps.storage, id);
+        Ok(())
+    }
+
+    /// Delete up to `limit` entities whose validity window has ended, in
+    /// order of expiry. Returns how many were deleted, and whether no expired
+    /// ones remain.
+    pub fn prune_expired(
+        &mut self,
+        limit: Option<u32>,
+    ) -> Result<(u32, bool), ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_JOB_BATCH_SIZE).clamp(1, MAX_JOB_BATCH_SIZE) as usize;
+        let now = self.env.block.time.nanos();
+        let expired = EXPIRY
+            .keys(
+                self.deps.storage,
+                None,
+                Some(Bound::inclusive((now, ObjectId::MAX))),
+                Order::Ascending,
+            )
+            .take(limit + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+        let done = expired.len() <= limit;
+
+        let schema = self.load_schema()?;
+        let mut n_pruned = 0;
+        for (expires_at, id) in expired.into_iter().take(limit) {
+            // An earlier cascade may have deleted it already
+            if !ENTITY.has(self.deps.storage, id) {
+                continue;
+            }
+            // Entities that can't be deleted while referenced stay hidden
+            // but are no longer tracked for expiry
+            if let Err(ContractError::Referenced { .. }) = self.require_deletable(id, &schema, &mut BTreeSet::new()) {
+                EXPIRY.remove(self.deps.storage, (expires_at, id));
+                continue;
+            }
+            self.delete_entity(DeleteArgs {
+                id: id.into(),
+                expected_revision: None,
+            })?;
+            n_pruned += 1;
+        }
+        Ok((n_pruned, done))
+    }
+
+    fn set_validity(
+        &mut self,
+        id: ObjectId,
+        validity: Validity,
+    ) -> Result<(), ContractError> {
+        if let (Some(valid_from), Some(expires_at)) = (validity.valid_from, validity.expires_at) {
+            if valid_from >= expires_at {
+                return Err(ContractError::ValidationError {
+                    reason: "validity window is empty".to_owned(),
+                });
+            }
+        }
+        self.unindex_expiry(id)?;
+        if let Some(expires_at) = validity.expires_at {
+            EXPIRY.save(self.deps.storage, (expires_at.nanos(), id), &1)?;
+        }
+        VALIDITY.save(self.deps.storage, id, &validity)?;
+        Ok(())
+    }
+
+    fn unindex_expiry(
+        &mut self,
+        id: ObjectId,
+    ) -> Result<(), ContractError> {
+        if let Some(expires_at) = VALIDITY.may_load(self.deps.storage, id)?.and_then(|v| v.expires_at) {
+            EXPIRY.remove(self.deps.storage, (expires_at.nanos(), id));
+        }
+        Ok(())
+    }
+
+    /// Replace an entity's data with that of a retained previous revision,
+    /// which is recorded as a new revision.
+    pub fn revert_entity(
+        &mut self,
+        id: Uint64,
+        revision: u32,
+    ) -> Result<(), ContractError> {
+        let EntityRevision { data, .. } =
+            HISTORY
+                .may_load(self.deps.storage, (id.u64(), revision))?
+                .ok_or_else(|| ContractError::NotFound {
+                    reason: format!("revision {} of entity {} not found", revision, id),
+                })?;
+        self.update_entity(UpdateArgs {
+            id,
+            data,
+            mode: UpdateMode::Replace,
+            expected_revision: None,
+            validity: None,
+        })
+    }
+
+    fn require_revision(
+        &self,
+        id: ObjectId,
+        expected_revision: Option<u32>,
+    ) -> Result<(), ContractError> {
+        if let Some(expected) = expected_revision {
+            let revision = load_revision(self.deps.storage, id)?;
+            if revision != expected {
+                return Err(ContractError::RevisionMismatch {
+                    reason: format!("entity {} is at revision {}, not {}", id, revision, expected),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Move an entity's current data into its history before it's
+    /// overwritten, dropping revisions beyond the retention depth.
+    fn save_revision(
+        &mut self,
+        id: ObjectId,
+        data: serde_json::Value,
+    ) -> Result<(), ContractError> {
+        let revision = load_revision(self.deps.storage, id)?;
+        let depth = CONFIG
+            .may_load(self.deps.storage)?
+            .unwrap_or_default()
+            .history_depth
+            .unwrap_or(DEFAULT_HISTORY_DEPTH);
+        if depth > 0 {
+            if let Some(time) = load_timestamp(self.deps.storage, UPDATED_AT_PROPERTY, id)? {
+                HISTORY.save(self.deps.storage, (id, revision), &EntityRevision { data, time })?;
+            }
+        }
+        if let Some(expired) = revision.checked_sub(depth) {
+            HISTORY.remove(self.deps.storage, (id, expired));
+        }
+        REVISION.save(self.deps.storage, id, &(revision + 1))?;
+        Ok(())
+    }
+
+    pub fn transfer_ownership(
+        &mut self,
+        args: TransferOwnershipArgs,
+    ) -> Result<(), ContractError> {
+        let TransferOwnershipArgs { id, owner } = args;
+        if !ENTITY.has(self.deps.storage, id.u64()) {
+            return Err(ContractError::NotFound {
+                reason: format!("entity {} not found", id.u64()),
+            });
+        }
+        self.set_owner(id.u64(), owner.as_ref())
+    }
+
+    /// Apply schema operations and queue a job for whatever they require of
+    /// existing entities. Returns true if a job was queued.
+    pub fn alter_schema(
+        &mut self,
+        operations: Vec<SchemaOperation>,
+    ) -> Result<bool, ContractError> {
+        if JOB.exists(self.deps.storage) {
+            return Err(ContractError::NotAuthorized {
+                reason: "another job is still in progress".to_owned(),
+            });
+        }
+
+        let mut schema = self.load_schema()?;
+        let mut steps: Vec<JobStep> = vec![];
+        let is_empty = COUNT.load(self.deps.storage)? == 0;
+
+        for op in operations {
+            match op {
+                SchemaOperation::AddProperty(prop) => {
+                    if schema.property(&prop.name).is_some() {
+                        return Err(ContractError::AlreadyExists {
+                            reason: format!("property {} already exists", prop.name),
+                        });
+                    }
+                    if let Some(default) = &prop.default {
+                        prop.validate(self.deps.api, default)?;
+                        let refs = EntitySchema {
+                            name: schema.name.to_owned(),
+                            properties: vec![prop.to_owned()],
+                            unknown_properties: None,
+                            max_byte_size: None,
+                            indexes: None,
+                        };
+                        if let Some((path, _)) = refs
+                            .paths()
+                            .into_iter()
+                            .find(|(_, p)| matches!(p.value, EntityPropertyParams::Ref { .. }))
+                        {
+                            return Err(ContractError::ValidationError {
+                                reason: format!("{} - reference can't have a default", path),
+                            });
+                        }
+                        if prop.unique.unwrap_or(false) && !is_empty {
+                            return Err(ContractError::ValidationError {
+                                reason: format!("{} - unique property can't have a default", prop.name),
+                            });
+                        }
+                        steps.push(JobStep::SetDefault {
+                            property: prop.name.to_owned(),
+                        });
+                    } else if prop.required.unwrap_or(false) && !is_empty {
+                        return Err(ContractError::ValidationError {
+                            reason: format!("{} - required property needs a default", prop.name),
+                        });
+                    }
+                    if prop.indexed.unwrap_or(false) {
+                        steps.push(JobStep::BuildIndex {
+                            property: prop.name.to_owned(),
+                        });
+                    }
+                    if prop.searchable.unwrap_or(false) {
+                        steps.push(JobStep::BuildSearchIndex {
+                            property: prop.name.to_owned(),
+                        });
+                    }
+                    // Existing entities only get nested fields from a default
+                    let nested = format!("{}.", prop.name);
+                    let has_default = prop.default.is_some();
+                    schema.properties.push(prop);
+                    if is_empty || !has_default {
+                        continue;
+                    }
+                    for (path, field) in schema.paths().into_iter().filter(|(path, _)| path.starts_with(&nested)) {
+                        if field.unique.unwrap_or(false) {
+                            return Err(ContractError::ValidationError {
+                                reason: format!("{} - unique field can't have a default", path),
+                            });
+                        }
+                        if field.indexed.unwrap_or(false) {
+                            steps.push(JobStep::BuildIndex {
+                                property: path.to_owned(),
+                            });
+                        }
+                        if field.searchable.unwrap_or(false) {
+                            steps.push(JobStep::BuildSearchIndex { property: path });
+                        }
+                    }
+                },
+                SchemaOperation::DropProperty { name } => {
+                    require_top_level(&schema, &name)?;
+                    let property = schema.property_mut(&name)?.to_owned();
+                    schema.properties.retain(|p| p.name != name);
+                    steps.push(JobStep::RemoveProperty { property });
+                },
+                SchemaOperation::RenameProperty { name, new_name } => {
+                    if schema.property(&new_name).is_some() {
+                        return Err(ContractError::AlreadyExists {
+                            reason: format!("property {} already exists", new_name),
+                        });
+                    }
+                    require_top_level(&schema, &name)?;
+                    let prop = schema.property_mut(&name)?;
+                    let property = prop.to_owned();
+                    prop.name = new_name.to_owned();
+                    steps.push(JobStep::RenameProperty { property, new_name });
+                },
+                SchemaOperation::SetIndexed { name, indexed } => {
+                    let prop = schema.property_mut(&name)?;
+                    if prop.indexed.unwrap_or(false) == indexed {
+                        continue;
+                    }
+                    // Nested fields are indexed by their path
+                    let property = EntityProperty {
+                        name: name.to_owned(),
+                        ..prop.to_owned()
+                    };
+                    prop.indexed = Some(indexed);
+                    steps.push(if indexed {
+                        JobStep::BuildIndex { property: name }
+                    } else {
+                        JobStep::DropIndex { property }
+                    });
+                },
+                SchemaOperation::SetIndexTransforms { name, transforms } => {
+                    let prop = schema.property_mut(&name)?;
+                    if prop.index_transforms == transforms {
+                        continue;
+                    }
+                    let property = EntityProperty {
+                        name: name.to_owned(),
+                        ..prop.to_owned()
+                    };
+                    prop.index_transforms = transforms;
+                    // Rewrite the index under the new keys
+                    if prop.indexed.unwrap_or(false) {
+                        steps.push(JobStep::DropIndex { property });
+                        steps.push(JobStep::BuildIndex { property: name });
+                    }
+                },
+                SchemaOperation::SetSearchable { name, searchable } => {
+                    let prop = schema.property_mut(&name)?;
+                    if prop.searchable.unwrap_or(false) == searchable {
+                        continue;
+                    }
+                    let property = EntityProperty {
+                        name: name.to_owned(),
+                        ..prop.to_owned()
+                    };
+                    prop.searchable = Some(searchable);
+                    steps.push(if searchable {
+                        JobStep::BuildSearchIndex { property: name }
+                    } else {
+                        JobStep::DropSearchIndex { property }
+                    });
+                },
+                SchemaOperation::SetRequired { name, required } => {
+                    // Only enforced on subsequent writes
+                    schema.property_mut(&name)?.required = Some(required);
+                },
+            }
+        }
+
+        schema.validate_indexes(self.deps.api)?;
+        SCHEMA.save(self.deps.storage, &schema)?;
+
+        if steps.is_empty() || is_empty {
+            return Ok(false);
+        }
+        start_job(self.deps.storage, vec![], steps)?;
+        Ok(true)
+    }
+
+    /// Queue a job that rebuilds an index from scratch.
+    pub fn rebuild_index(
+        &mut self,
+        property: String,
+    ) -> Result<(), ContractError> {
+        let schema = self.load_schema()?;
+        let prop = schema.property(&property).ok_or_else(|| ContractError::NotFound {
+            reason: format!("property {} not found", property),
+        })?;
+        if !prop.indexed.unwrap_or(false) {
+            return Err(ContractError::ValidationError {
+                reason: format!("property {} not indexed", property),
+            });
+        }
+        start_job(
+            self.deps.storage,
+            vec![property.to_owned()],
+            vec![JobStep::BuildIndex { property }],
+        )
+    }
+
+    /// Advance the pending job by up to `limit` units of work, each either an
+    /// index entry cleared or an entity processed. Returns the number of units
+    /// done, the entities left out of an index because their stored values
+    /// couldn't be encoded, and whether the job is now complete.
+    pub fn continue_job(
+        &mut self,
+        limit: Option<u32>,
+    ) -> Result<(u32, Vec<ObjectId>, bool), ContractError> {
+        let mut job = JOB
+            .may_load(self.deps.storage)?
+            .ok_or_else(|| ContractError::NotFound {
+                reason: "no job in progress".to_owned(),
+            })?;
+        let limit = limit.unwrap_or(DEFAULT_JOB_BATCH_SIZE).clamp(1, MAX_JOB_BATCH_SIZE) as usize;
+        let mut budget = limit;
+
+        // Empty out indices being rebuilt
+        while let Some(index_name) = job.clear_indices.first().map(|name| format!("_ix_{}", name)) {
+            let index = PropertyIndex::new(&index_name);
+            let keys = index
+                .keys(self.deps.storage, None, None, Order::Ascending)
+                .take(budget)
+                .collect::<Result<Vec<_>, _>>()?;
+            budget -= keys.len();
+            for (key, id) in keys.iter() {
+                index.remove(self.deps.storage, (key, *id));
+            }
+            if budget == 0 {
+                JOB.save(self.deps.storage, &job)?;
+                return Ok((limit as u32, vec![], false));
+            }
+            job.clear_indices.remove(0);
+        }
+
+        let schema = self.load_schema()?;
+        let entries = ENTITY
+            .range(
+                self.deps.storage,
+                job.cursor.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(budget)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n = entries.len();
+        let mut skipped = vec![];
+        for (id, mut data) in entries {
+            let migrated = MIGRATED.may_load(self.deps.storage, id)?;
+            MIGRATED.remove(self.deps.storage, id);
+            if migrated != Some(job.seq) {
+                let applied = self.apply_job_steps(&job.steps, &schema, id, &mut data)?;
+                if applied.changed {
+                    ENTITY.save(self.deps.storage, id, &data)?;
+                }
+                if applied.skipped {
+                    skipped.push(id);
+                }
+            }
+            job.cursor = Some(id);
+            job.processed += 1;
+        }
+        job.skipped += skipped.len() as u32;
+
+        let n_done = (limit - budget + n) as u32;
+        if n < budget {
+            JOB.remove(self.deps.storage);
+            Ok((n_done, skipped, true))
+        } else {
+            JOB.save(self.deps.storage, &job)?;
+            Ok((n_done, skipped, false))
+        }
+    }
+
+    /// Bring an entity the pending job hasn't reached up to date with the
+    /// current schema. The caller is responsible for saving the result.
+    fn apply_pending_job(
+        &mut self,
+        id: ObjectId,
+        data: serde_json::Value,
+        schema: &EntitySchema,
+    ) -> Result<serde_json::Value, ContractError> {
+        let mut data = data;
+        if let Some(job) = JOB.may_load(self.deps.storage)? {
+            if job.cursor.map(|c| id > c).unwrap_or(true) && MIGRATED.may_load(self.deps.storage, id)? != Some(job.seq)
+            {
+                self.apply_job_steps(&job.steps, schema, id, &mut data)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Record that an entity was just saved against the current schema, so
+    /// the pending job leaves it alone when the cursor gets to it.
+    fn mark_migrated(
+        &mut self,
+        id: ObjectId,
+    ) -> Result<(), ContractError> {
+        if let Some(job) = JOB.may_load(self.deps.storage)? {
+            if job.cursor.map(|c| id > c).unwrap_or(true) {
+                MIGRATED.save(self.deps.storage, id, &job.seq)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply job steps to one entity.
+    fn apply_job_steps(
+        &mut self,
+        steps: &[JobStep],
+        schema: &EntitySchema,
+        id: ObjectId,
+        data: &mut serde_json::Value,
+    ) -> Result<AppliedSteps, ContractError> {
+        let values = data.as_object_mut().ok_or_else(|| ContractError::Unexpected {
+            reason: "entity data not an object".to_owned(),
+        })?;
+        let mut changed = false;
+        let mut skipped = false;
+
+        for step in steps.iter() {
+            match step {
+                JobStep::SetDefault { property } => {
+                    let default = schema.property(property).and_then(|p| p.default.to_owned());
+                    if let (Some(default), false) = (default, values.contains_key(property)) {
+                        values.insert(property.to_owned(), default);
+                        changed = true;
+                    }
+                },
+                JobStep::RemoveProperty { property } => {
+                    if let Some(value) = values.remove(&property.name) {
+                        self.update_property_indices(id, &property.name, property, Some(&value), None)?;
+                        changed = true;
+                    }
+                },
+                JobStep::RenameProperty { property, new_name } => {
+                    if let Some(value) = values.remove(&property.name) {
+                        self.update_property_indices(id, &property.name, property, Some(&value), None)?;
+                        if let Some(prop) = schema.property(new_name) {
+                            let indexed = self.update_property_indices(id, new_name, prop, None, Some(&value));
+                            skipped |= encoded(indexed)?.is_none();
+                        }
+                        values.insert(new_name.to_owned(), value);
+                        changed = true;
+                    }
+                },
+                JobStep::BuildIndex { property } => {
+                    let index_name = format!("_ix_{}", property);
+                    if let (Some(prop), Some(value)) = (schema.property(property), lookup(values, property)) {
+                        if prop.indexed.unwrap_or(false) {
+                            let index = PropertyIndex::new(&index_name);
+                            match encoded(prop.index_keys(value))? {
+                                Some(keys) => {
+                                    for key in keys {
+                                        index.save(self.deps.storage, (&key, id), &1)?;
+                                    }
+                                },
+                                None => skipped = true,
+                            }
+                        }
+                    } else if schema.property(property).is_none() && timestamp_property(property).is_some() {
+                        if let Some(time) = load_timestamp(self.deps.storage, property, id)? {
+                            PropertyIndex::new(&index_name).save(
+                                self.deps.storage,
+                                (&time.nanos().to_be_bytes(), id),
+                                &1,
+                            )?;
+                        }
+                    }
+                },
+                JobStep::BuildSearchIndex { property } => {
+                    if let (Some(prop), Some(value)) = (schema.property(property), lookup(values, property)) {
+                        if prop.searchable.unwrap_or(false) {
+                            let index_name = format!("_tx_{}", property);
+                            let index = PropertyIndex::new(&index_name);
+                            match encoded(prop.search_tokens(value))? {
+                                Some(tokens) => {
+                                    for token in tokens {
+                                        index.save(self.deps.storage, (token.as_bytes(), id), &1)?;
+                                    }
+                                },
+                                None => skipped = true,
+                            }
+                        }
+                    }
+                },
+                JobStep::DropSearchIndex { property } => {
+                    if let Some(value) = lookup(values, &property.name) {
+                        let index_name = format!("_tx_{}", property.name);
+                        let index = PropertyIndex::new(&index_name);
+                        for token in encoded(property.search_tokens(value))?.unwrap_or_default() {
+                            index.remove(self.deps.storage, (token.as_bytes(), id));
+                        }
+                    }
+                },
+                JobStep::DropIndex { property } => {
+                    if let Some(value) = lookup(values, &property.name) {
+                        let index_name = format!("_ix_{}", property.name);
+                        let index = PropertyIndex::new(&index_name);
+                        for key in encoded(property.index_keys(value))?.unwrap_or_default() {
+                            index.remove(self.deps.storage, (&key, id));
+                        }
+                    }
+                },
+            }
+        }
+        Ok(AppliedSteps { changed, skipped })
+    }
+
     pub fn load_schema(&self) -> Result<EntitySchema, ContractError> {
         Ok(SCHEMA.load(self.deps.storage)?)
     }
@@ -212,29 +1517,235 @@ impl<'a> ExecuteContext<'a> {
             reason: "entity data not an object".to_owned(),
         })?;
         // Remove all props from index if any
-        for prop in schema.properties.iter() {
-            let EntityProperty { name, .. } = prop;
-            let index_name = format!("_ix_{}", name);
-            if let Some(value) = values.get(name) {
-                // Get or create index
-                let index = PropertyIndex::new(&index_name);
-                // Remove from index
-                index.remove(self.deps.storage, (&prop.to_bytes(value)?, id));
+        for (name, prop) in schema.paths() {
+            self.update_property_indices(id, &name, prop, lookup(values, &name), None)?;
+        }
+        for index in schema.indexes.iter().flatten() {
+            if let Some(key) = encoded(schema.compound_key(index, values))?.flatten() {
+                let index_name = format!("_cx_{}", index.name);
+                PropertyIndex::new(&index_name).remove(self.deps.storage, (&key, id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail unless each reference the entity gains points at an entity that
+    /// exists, in this collection or in the referenced contract.
+    fn require_refs(
+        &self,
+        id: ObjectId,
+        old_entity: &serde_json::Value,
+        new_entity: &serde_json::Value,
+        schema: &EntitySchema,
+    ) -> Result<(), ContractError> {
+        for (name, prop) in schema.paths() {
+            let EntityPropertyParams::Ref { contract, .. } = &prop.value else {
+                continue;
+            };
+            let value = match lookup_value(new_entity, &name) {
+                Some(value) if lookup_value(old_entity, &name) != Some(value) => value,
+                _ => continue,
+            };
+            let target = prop.ref_id(value)?;
+            let exists = match contract {
+                None => target == id || ENTITY.has(self.deps.storage, target),
+                Some(contract) => read_remote(self.deps.querier, contract, target, None, None, true)?.is_some(),
+            };
+            if !exists {
+                return Err(ContractError::NotFound {
+                    reason: format!("{} - entity {} not found", name, target),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// IDs of other entities referencing an entity through a reference
+    /// property, up to `limit`. An entity referencing itself doesn't count,
+    /// since it goes away along with its reference.
+    fn load_referrers(
+        &self,
+        id: ObjectId,
+        path: &str,
+        limit: usize,
+    ) -> Result<Vec<ObjectId>, ContractError> {
+        let index_name = format!("_rx_{}", path);
+        Ok(PropertyIndex::new(&index_name)
+            .prefix(&id.to_be_bytes())
+            .keys(self.deps.storage, None, None, Order::Ascending)
+            .filter(|referrer| referrer.as_ref().map_or(true, |referrer| *referrer != id))
+            .take(limit)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Fail if an entity is referenced from within the collection through a
+    /// property whose delete policy is restrict.
+    fn require_unreferenced(
+        &self,
+        id: ObjectId,
+        schema: &EntitySchema,
+    ) -> Result<(), ContractError> {
+        let incomplete = JOB
+            .may_load(self.deps.storage)?
+            .map(|job| job.incomplete_indices())
+            .unwrap_or_default();
+        for (path, prop) in schema.paths() {
+            let EntityPropertyParams::Ref {
+                contract: None,
+                on_delete,
+            } = prop.value
+            else {
+                continue;
+            };
+            if incomplete.contains(&format!("_rx_{}", path)) {
+                return Err(ContractError::NotAuthorized {
+                    reason: format!("references through {} are being rebuilt", path),
+                });
+            }
+            if on_delete.unwrap_or_default() != RefPolicy::Restrict {
+                continue;
+            }
+            if let Some(referrer) = self.load_referrers(id, &path, 1)?.first() {
+                return Err(ContractError::Referenced {
+                    reason: format!("entity {} is referenced by entity {} through {}", id, referrer, path),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail if deleting an entity would be refused, either by its own
+    /// referrers or by those of the entities the deletion cascades to.
+    fn require_deletable(
+        &self,
+        id: ObjectId,
+        schema: &EntitySchema,
+        visited: &mut BTreeSet<ObjectId>,
+    ) -> Result<(), ContractError> {
+        if !visited.insert(id) {
+            return Ok(());
+        }
+        self.require_unreferenced(id, schema)?;
+        for (path, prop) in schema.paths() {
+            let EntityPropertyParams::Ref {
+                contract: None,
+                on_delete: Some(policy @ (RefPolicy::Cascade | RefPolicy::SetNull)),
+            } = prop.value
+            else {
+                continue;
+            };
+            let referrers = self.load_policy_referrers(id, &path)?;
+            if policy == RefPolicy::Cascade {
+                for referrer in referrers {
+                    self.require_deletable(referrer, schema, visited)?;
+                }
             }
         }
         Ok(())
     }
 
+    /// IDs of entities a delete policy applies to when an entity is deleted,
+    /// failing if there are too many to handle in one transaction.
+    fn load_policy_referrers(
+        &self,
+        id: ObjectId,
+        path: &str,
+    ) -> Result<Vec<ObjectId>, ContractError> {
+        let referrers = self.load_referrers(id, path, MAX_REFERRERS + 1)?;
+        if referrers.len() > MAX_REFERRERS {
+            return Err(ContractError::Referenced {
+                reason: format!("entity {} has over {} references through {}", id, MAX_REFERRERS, path),
+            });
+        }
+        Ok(referrers)
+    }
+
+    /// Cascade the deletion of an entity to the entities referencing it, or
+    /// remove their references, as their properties' delete policies say.
+    fn apply_delete_policies(
+        &mut self,
+        id: ObjectId,
+        schema: &EntitySchema,
+    ) -> Result<(), ContractError> {
+        for (path, prop) in schema.paths() {
+            let EntityPropertyParams::Ref {
+                contract: None,
+                on_delete: Some(policy @ (RefPolicy::Cascade | RefPolicy::SetNull)),
+            } = prop.value
+            else {
+                continue;
+            };
+            for referrer in self.load_policy_referrers(id, &path)? {
+                // An earlier cascade may have deleted it already
+                let Some(mut data) = ENTITY.may_load(self.deps.storage, referrer)? else {
+                    continue;
+                };
+                if policy == RefPolicy::Cascade {
+                    self.delete_entity(DeleteArgs {
+                        id: referrer.into(),
+                        expected_revision: None,
+                    })?;
+                } else {
+                    if let Some(values) = data.as_object_mut() {
+                        remove_path(values, &path);
+                    }
+                    self.update_entity(UpdateArgs {
+                        id: referrer.into(),
+                        data,
+                        mode: UpdateMode::Replace,
+                        expected_revision: None,
+                        validity: None,
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail if any unique property of the entity has a value already held by
+    /// another entity, listing each conflict.
+    fn require_unique(
+        &self,
+        id: ObjectId,
+        data: &serde_json::Value,
+        schema: &EntitySchema,
+    ) -> Result<(), ContractError> {
+        let incomplete = JOB
+            .may_load(self.deps.storage)?
+            .map(|job| job.incomplete_indices())
+            .unwrap_or_default();
+        let mut conflicts: Vec<String> = vec![];
+        for (name, prop) in schema.paths().into_iter().filter(|(_, p)| p.unique.unwrap_or(false)) {
+            if let Some(value) = lookup_value(data, &name) {
+                let index_name = format!("_ux_{}", name);
+                if incomplete.contains(&index_name) {
+                    return Err(ContractError::NotAuthorized {
+                        reason: format!("unique index {} is being rebuilt", name),
+                    });
+                }
+                let owner_id = UniqueIndex::new(&index_name).may_load(self.deps.storage, &prop.to_bytes(value)?)?;
+                if let Some(owner_id) = owner_id.filter(|x| *x != id) {
+                    conflicts.push(format!("{} - value already used by entity {}", name, owner_id));
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(ContractError::AlreadyExists {
+                reason: conflicts.join("; "),
+            });
+        }
+        Ok(())
+    }
+
+    /// Bring indices in line with a change from one complete, validated
+    /// version of an entity to another.
     pub fn update_indices(
         &mut self,
         id: &Uint64,
         old_entity: &serde_json::Value,
         new_entity: &serde_json::Value,
         schema: &EntitySchema,
-        mode: Option<UpdateMode>,
     ) -> Result<(), ContractError> {
-        let mode = mode.unwrap_or(UpdateMode::Replace);
-
         let old_values = old_entity.as_object().ok_or_else(|| ContractError::ValidationError {
             reason: "existing entity is not an object".to_owned(),
         })?;
@@ -243,35 +1754,198 @@ impl<'a> ExecuteContext<'a> {
             reason: "updated entity is not an object".to_owned(),
         })?;
 
-        for prop in schema.properties.iter() {
-            let EntityProperty {
-                name,
-                required,
-                indexed,
-                ..
-            } = prop;
-            let index_name = format!("_ix_{}", name);
-            if let Some(new_value) = new_values.get(name) {
-                // Get or create index
-                if indexed.unwrap_or(false) {
-                    let index = PropertyIndex::new(&index_name);
-                    // Remove old node in index
-                    if let Some(old_value) = old_values.get(name) {
-                        if *old_value == *new_value {
-                            continue; // Skip updating
-                        }
-                        index.remove(self.deps.storage, (&prop.to_bytes(old_value)?, id.u64()));
-                    }
-                    // Set new node in index
-                    let new_key = prop.to_bytes(new_value)?;
-                    index.save(self.deps.storage, (&new_key, id.u64()), &1)?;
-                }
-            } else if mode == UpdateMode::Replace && required.unwrap_or(false) {
-                return Err(ContractError::ValidationError {
-                    reason: format!("{} required", name),
-                });
+        for (name, prop) in schema.paths() {
+            let old_value = lookup(old_values, &name);
+            let new_value = lookup(new_values, &name);
+            if old_value == new_value {
+                continue; // Skip updating
+            }
+            self.update_property_indices(id.u64(), &name, prop, old_value, new_value)?;
+        }
+
+        for compound_index in schema.indexes.iter().flatten() {
+            let old_key = encoded(schema.compound_key(compound_index, old_values))?.flatten();
+            let new_key = schema.compound_key(compound_index, new_values)?;
+            if old_key == new_key {
+                continue;
+            }
+            let index_name = format!("_cx_{}", compound_index.name);
+            let index = PropertyIndex::new(&index_name);
+            if let Some(old_key) = old_key {
+                index.remove(self.deps.storage, (&old_key, id.u64()));
+            }
+            if let Some(new_key) = new_key {
+                index.save(self.deps.storage, (&new_key, id.u64()), &1)?;
             }
         }
         Ok(())
     }
+    /// Move an entity's entries for one property in the property, unique,
+    /// search and reference indexes from its old value to its new one.
+    fn update_property_indices(
+        &mut self,
+        id: ObjectId,
+        path: &str,
+        prop: &EntityProperty,
+        old_value: Option<&serde_json::Value>,
+        new_value: Option<&serde_json::Value>,
+    ) -> Result<(), ContractError> {
+        let EntityProperty {
+            indexed,
+            unique,
+            searchable,
+            ..
+        } = prop;
+        if unique.unwrap_or(false) {
+            let index_name = format!("_ux_{}", path);
+            let index = UniqueIndex::new(&index_name);
+            // Nothing was indexed for a value that can't be encoded
+            if let Some(old_key) = old_value.map(|v| encoded(prop.to_bytes(v))).transpose()?.flatten() {
+                index.remove(self.deps.storage, &old_key);
+            }
+            if let Some(new_value) = new_value {
+                index.save(self.deps.storage, &prop.to_bytes(new_value)?, &id)?;
+            }
+        }
+        if searchable.unwrap_or(false) {
+            let index_name = format!("_tx_{}", path);
+            let index = PropertyIndex::new(&index_name);
+            let old_tokens = match old_value {
+                Some(old_value) => encoded(prop.search_tokens(old_value))?.unwrap_or_default(),
+                None => BTreeSet::new(),
+            };
+            let new_tokens = match new_value {
+                Some(new_value) => prop.search_tokens(new_value)?,
+                None => BTreeSet::new(),
+            };
+            // Only write the words that changed
+            for token in old_tokens.difference(&new_tokens) {
+                index.remove(self.deps.storage, (token.as_bytes(), id));
+            }
+            for token in new_tokens.difference(&old_tokens) {
+                index.save(self.deps.storage, (token.as_bytes(), id), &1)?;
+            }
+        }
+        if let EntityPropertyParams::Ref { .. } = prop.value {
+            let index_name = format!("_rx_{}", path);
+            let index = PropertyIndex::new(&index_name);
+            if let Some(old_key) = old_value.map(|v| encoded(prop.to_bytes(v))).transpose()?.flatten() {
+                index.remove(self.deps.storage, (&old_key, id));
+            }
+            if let Some(new_value) = new_value {
+                index.save(self.deps.storage, (&prop.to_bytes(new_value)?, id), &1)?;
+            }
+        }
+        if indexed.unwrap_or(false) {
+            let index_name = format!("_ix_{}", path);
+            let index = PropertyIndex::new(&index_name);
+            let old_keys = match old_value {
+                Some(old_value) => encoded(prop.index_keys(old_value))?.unwrap_or_default(),
+                None => BTreeSet::new(),
+            };
+            let new_keys = match new_value {
+                Some(new_value) => prop.index_keys(new_value)?,
+                None => BTreeSet::new(),
+            };
+            // Remove old nodes in index
+            for old_key in old_keys.difference(&new_keys) {
+                index.remove(self.deps.storage, (old_key, id));
+            }
+            // Set new nodes in index
+            for new_key in new_keys.difference(&old_keys) {
+                index.save(self.deps.storage, (new_key, id), &1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What applying the job steps did to an entity.
+struct AppliedSteps {
+    /// Its data changed and needs saving
+    changed: bool,
+    /// Some of its values couldn't be indexed
+    skipped: bool,
+}
+
+/// The index encoding of a stored value, or None if it can't be encoded, as
+/// is the case for values stored before they were checked as strictly.
+fn encoded<T>(result: Result<T, ContractError>) -> Result<Option<T>, ContractError> {
+    match result {
+        Ok(encoding) => Ok(Some(encoding)),
+        Err(ContractError::ValidationError { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// An entity's current revision. Entities start at revision 1, which is
+/// implicit for those never updated.
+pub fn load_revision(
+    storage: &dyn Storage,
+    id: ObjectId,
+) -> Result<u32, ContractError> {
+    Ok(REVISION.may_load(storage, id)?.unwrap_or(1))
+}
+
+/// The pseudo-property for an entity timestamp, if `name` is one.
+pub fn timestamp_property(name: &str) -> Option<EntityProperty> {
+    [CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY]
+        .contains(&name)
+        .then(|| EntityProperty {
+            indexed: Some(true),
+            required: None,
+            unique: None,
+            index_transforms: None,
+            searchable: None,
+            default: None,
+            value: EntityPropertyParams::U64 {},
+            name: name.to_owned(),
+        })
+}
+
+/// Load the time behind a timestamp pseudo-property. Entities that haven't
+/// been written since they were created count as updated at creation.
+pub fn load_timestamp(
+    storage: &dyn Storage,
+    name: &str,
+    id: ObjectId,
+) -> Result<Option<Timestamp>, ContractError> {
+    let created_at = CREATED_AT.may_load(storage, id)?;
+    Ok(match name {
+        CREATED_AT_PROPERTY => created_at,
+        _ => UPDATED_AT.may_load(storage, id)?.or(created_at),
+    })
+}
+
+/// Queue a rebuild of every `_ix_*` index if it was written with an older
+/// key encoding. Returns true if a rebuild was queued.
+pub fn migrate_index_encoding(storage: &mut dyn Storage) -> Result<bool, ContractError> {
+    let version = INDEX_ENCODING.may_load(storage)?.unwrap_or_default();
+    if version >= INDEX_ENCODING_VERSION {
+        return Ok(false);
+    }
+    let mut clear_indices: Vec<String> = vec![];
+    let mut steps: Vec<JobStep> = vec![];
+    if version < 1 {
+        let schema = SCHEMA.load(storage)?;
+        // Clear non-indexed props too, since they may have stale entries left
+        // over from earlier versions.
+        clear_indices = schema.properties.iter().map(|p| p.name.to_owned()).collect();
+        steps = schema
+            .properties
+            .iter()
+            .filter(|p| p.indexed.unwrap_or(false))
+            .map(|p| JobStep::BuildIndex {
+                property: p.name.to_owned(),
+            })
+            .collect();
+    }
+    for name in [CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY] {
+        steps.push(JobStep::BuildIndex {
+            property: name.to_owned(),
+        });
+    }
+    start_job(storage, clear_indices, steps)?;
+    INDEX_ENCODING.save(storage, &INDEX_ENCODING_VERSION)?;
+    Ok(true)
 }
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{create, query_json, setup};
+use contract::responses::AggregateResponse;
+use cosmwasm_std::{Int256, Uint64};
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "orders", "properties": [
+        {"name": "qty", "value": {"i64": {}}},
+        {"name": "price", "value": {"decimal": {"scale": 2}}},
+        {"name": "at", "value": {"timestamp": {}}},
+        {"name": "ratio", "value": {"f64": {}}},
+    ]})
+}
+
+fn aggregate(
+    app: &cw_multi_test::App,
+    addr: &cosmwasm_std::Addr,
+    property: &str,
+) -> AggregateResponse {
+    let response = query_json(
+        app,
+        addr,
+        json!({"aggregate": {"target": {"ids": ["1", "2", "3"]}, "property": property}}),
+    );
+    serde_json::from_value(response).unwrap()
+}
+
+#[test]
+fn aggregates_integers_decimals_and_timestamps() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"qty": -4, "price": "1.50", "at": "3000"}));
+    create(&mut app, &addr, 2, json!({"qty": 10, "price": "-0.25", "at": "1000"}));
+    create(&mut app, &addr, 3, json!({}));
+
+    let qty = aggregate(&app, &addr, "qty");
+    assert_eq!(qty.accumulator.count, Uint64::new(2));
+    assert_eq!(qty.accumulator.sum, Int256::from(6i128));
+    assert_eq!(qty.accumulator.min, Some(Int256::from(-4i128)));
+
+    let price = aggregate(&app, &addr, "price");
+    assert_eq!(price.accumulator.sum, Int256::from(125i128));
+    assert_eq!(price.accumulator.min, Some(Int256::from(-25i128)));
+    assert_eq!(price.accumulator.max, Some(Int256::from(150i128)));
+
+    let at = aggregate(&app, &addr, "at");
+    assert_eq!(at.accumulator.min, Some(Int256::from(1000i128)));
+    assert_eq!(at.accumulator.max, Some(Int256::from(3000i128)));
+}
+
+#[test]
+fn rejects_non_numeric_property() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"qty": 1}));
+    let msg = json!({"aggregate": {"target": {"ids": ["1"]}, "property": "ratio"}});
+    assert!(app.wrap().query_wasm_smart::<AggregateResponse>(&addr, &msg).is_err());
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::{create, exec, exec_err, query_json, read_ids, read_ids_of, setup, store_code, OPERATOR};
+use contract::{error::ContractError, msg::InstantiateMsg, schema::EntitySchema};
+use cosmwasm_std::Addr;
+use cw_multi_test::{App, Executor};
+use serde_json::json;
+
+fn alter(
+    app: &mut App,
+    addr: &Addr,
+    operations: serde_json::Value,
+) {
+    exec(app, addr, OPERATOR, json!({"alter_schema": {"operations": operations}})).unwrap();
+}
+
+fn finish_job(
+    app: &mut App,
+    addr: &Addr,
+) {
+    exec(app, addr, OPERATOR, json!({"continue_job": {"limit": 100}})).unwrap();
+}
+
+#[test]
+fn rename_leaves_entities_created_during_job_alone() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "a", "value": {"u64": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    create(&mut app, &addr, 1, json!({"a": 1}));
+    create(&mut app, &addr, 2, json!({"a": 2}));
+    alter(
+        &mut app,
+        &addr,
+        json!([{"rename_property": {"name": "a", "new_name": "b"}}]),
+    );
+
+    create(&mut app, &addr, 3, json!({"b": 5, "a": "junk"}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "2", "data": {"b": 7, "a": "junk"}, "mode": "merge"}}),
+    )
+    .unwrap();
+    finish_job(&mut app, &addr);
+
+    let entities = read_ids(&app, &addr, &[1, 2, 3]).entities;
+    assert_eq!(entities[0].data, Some(json!({"b": 1})));
+    assert_eq!(entities[1].data, Some(json!({"b": 7, "a": "junk"})));
+    assert_eq!(entities[2].data, Some(json!({"b": 5, "a": "junk"})));
+}
+
+#[test]
+fn dropped_index_ignores_entities_created_during_job() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "n", "indexed": true, "value": {"u64": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    create(&mut app, &addr, 1, json!({"n": 1}));
+    alter(&mut app, &addr, json!([{"drop_property": {"name": "n"}}]));
+
+    create(&mut app, &addr, 2, json!({"n": "text"}));
+    finish_job(&mut app, &addr);
+
+    let job = query_json(&app, &addr, json!({"job": {}}));
+    assert_eq!(job, json!(null));
+    let entities = read_ids(&app, &addr, &[1, 2]).entities;
+    assert_eq!(entities[0].data, Some(json!({})));
+    assert_eq!(entities[1].data, Some(json!({"n": "text"})));
+}
+
+#[test]
+fn defaults_must_fit_their_property() {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    let schema: EntitySchema = serde_json::from_value(json!({"name": "x", "properties": [
+        {"name": "a", "value": {"object": {"properties": [
+            {"name": "b", "default": "text", "value": {"u8": {}}},
+        ]}}},
+    ]}))
+    .unwrap();
+    let msg = InstantiateMsg {
+        schema,
+        operator: None,
+        metadata: None,
+        config: None,
+    };
+    let result = app.instantiate_contract(code_id, Addr::unchecked(OPERATOR), &msg, &[], "x", None);
+    assert!(result.is_err());
+
+    let schema = json!({"name": "things", "properties": [
+        {"name": "a", "value": {"u64": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    for prop in [
+        json!({"name": "b", "default": 300, "value": {"u8": {}}}),
+        json!({"name": "c", "value": {"object": {"properties": [
+            {"name": "d", "default": -1, "value": {"u8": {}}},
+        ]}}}),
+    ] {
+        let err = exec_err(
+            &mut app,
+            &addr,
+            OPERATOR,
+            json!({"alter_schema": {"operations": [{"add_property": prop}]}}),
+        );
+        assert!(matches!(err, ContractError::ValidationError { .. }));
+    }
+}
+
+#[test]
+fn added_property_is_backfilled_and_indexed() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "a", "value": {"u64": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    create(&mut app, &addr, 1, json!({"a": 1}));
+    create(&mut app, &addr, 2, json!({"a": 2, "level": 9}));
+    alter(
+        &mut app,
+        &addr,
+        json!([
+            {"add_property": {"name": "level", "indexed": true, "default": 5, "value": {"u8": {}}}},
+            {"set_required": {"name": "a", "required": true}},
+        ]),
+    );
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "3", "data": {"level": 1}}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+    finish_job(&mut app, &addr);
+
+    let entities = read_ids(&app, &addr, &[1, 2]).entities;
+    assert_eq!(entities[0].data, Some(json!({"a": 1, "level": 5})));
+    let ids = read_ids_of(&app, &addr, json!({"target": {"range": {"property": "level"}}}));
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn dropped_property_clears_its_index() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "a", "value": {"u64": {}}},
+        {"name": "n", "indexed": true, "value": {"u64": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    for id in 1..=3 {
+        create(&mut app, &addr, id, json!({"a": id, "n": id}));
+    }
+    alter(&mut app, &addr, json!([{"drop_property": {"name": "n"}}]));
+    finish_job(&mut app, &addr);
+    let entities = read_ids(&app, &addr, &[1]).entities;
+    assert_eq!(entities[0].data, Some(json!({"a": 1})));
+
+    // Adding it back starts from an empty index
+    alter(
+        &mut app,
+        &addr,
+        json!([{"add_property": {"name": "n", "indexed": true, "value": {"u64": {}}}}]),
+    );
+    finish_job(&mut app, &addr);
+    let ids = read_ids_of(&app, &addr, json!({"target": {"range": {"property": "n"}}}));
+    assert_eq!(ids, Vec::<u64>::new());
+}
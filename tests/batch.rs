@@ -0,0 +1,74 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids, read_ids_of, setup, OPERATOR};
+use contract::{
+    error::ContractError,
+    responses::{BatchOperationResult, BatchResponse},
+};
+use cosmwasm_std::{from_json, Uint64};
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "items", "properties": [
+        {"name": "sku", "unique": true, "value": {"string": {}}},
+        {"name": "qty", "indexed": true, "value": {"u32": {}}},
+    ]})
+}
+
+#[test]
+fn batch_applies_mixed_operations_in_order() {
+    let (mut app, addr) = setup(schema(), Some(json!({"id_strategy": "sequence"})));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"data": {"sku": "a", "qty": 1}}}),
+    )
+    .unwrap();
+    let response = exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"batch": [
+            {"create": {"data": {"sku": "b", "qty": 2}}},
+            {"update": {"id": "2", "data": {"qty": 5}, "mode": "merge"}},
+            {"delete": {"id": "1"}},
+            // The deleted entity's unique value is free again
+            {"create": {"data": {"sku": "a", "qty": 3}}},
+        ]}),
+    )
+    .unwrap();
+    let BatchResponse { results } = from_json(response.data.unwrap()).unwrap();
+    assert_eq!(
+        results,
+        vec![
+            BatchOperationResult::Create { id: Uint64::new(2) },
+            BatchOperationResult::Update { id: Uint64::new(2) },
+            BatchOperationResult::Delete { id: Uint64::new(1) },
+            BatchOperationResult::Create { id: Uint64::new(3) },
+        ]
+    );
+    let ids = read_ids_of(&app, &addr, json!({"target": {"range": {"property": "qty"}}}));
+    assert_eq!(ids, vec![3, 2]);
+}
+
+#[test]
+fn failed_operation_reverts_the_batch() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"sku": "a", "qty": 1}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"batch": [
+            {"create": {"id": "2", "data": {"sku": "b", "qty": 2}}},
+            {"update": {"id": "1", "data": {"qty": 7}, "mode": "merge"}},
+            {"create": {"id": "3", "data": {"sku": "b", "qty": 3}}},
+        ]}),
+    );
+    assert!(matches!(err, ContractError::BatchOperationFailed { index: 2, .. }));
+
+    let entities = read_ids(&app, &addr, &[1, 2]).entities;
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].data, Some(json!({"sku": "a", "qty": 1})));
+}
@@ -0,0 +1,246 @@
+#![allow(dead_code)]
+
+use contract::{
+    contract::{execute, instantiate, migrate, query},
+    error::ContractError,
+    msg::{CreateArgs, ExecuteMsg, InstantiateMsg, QueryMsg, ReadArgs, ReadTarget},
+    responses::ReadResponse,
+    schema::EntitySchema,
+    state::CollectionConfig,
+};
+use cosmwasm_std::{Addr, Order, Record, Storage, Uint64};
+use cw_multi_test::{error::AnyResult, App, AppResponse, ContractWrapper, Executor};
+use serde_json::{json, Value};
+
+pub const OPERATOR: &str = "operator";
+
+pub fn store_code(app: &mut App) -> u64 {
+    app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(migrate),
+    ))
+}
+
+/// Instantiate a collection with the given schema and config, as `OPERATOR`,
+/// who is also its admin.
+pub fn instantiate_collection(
+    app: &mut App,
+    code_id: u64,
+    schema: Value,
+    config: Option<Value>,
+) -> Addr {
+    let schema: EntitySchema = serde_json::from_value(schema).unwrap();
+    let config: Option<CollectionConfig> = config.map(|c| serde_json::from_value(c).unwrap());
+    let msg = InstantiateMsg {
+        schema,
+        operator: None,
+        metadata: None,
+        config,
+    };
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(OPERATOR),
+        &msg,
+        &[],
+        "collection",
+        Some(OPERATOR.to_owned()),
+    )
+    .unwrap()
+}
+
+/// A fresh app with one collection.
+pub fn setup(
+    schema: Value,
+    config: Option<Value>,
+) -> (App, Addr) {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    let addr = instantiate_collection(&mut app, code_id, schema, config);
+    (app, addr)
+}
+
+pub fn exec(
+    app: &mut App,
+    contract: &Addr,
+    sender: &str,
+    msg: Value,
+) -> AnyResult<AppResponse> {
+    let msg: ExecuteMsg = serde_json::from_value(msg).unwrap();
+    app.execute_contract(Addr::unchecked(sender), contract.to_owned(), &msg, &[])
+}
+
+/// Execute a message expected to fail, returning the contract's error.
+pub fn exec_err(
+    app: &mut App,
+    contract: &Addr,
+    sender: &str,
+    msg: Value,
+) -> ContractError {
+    exec(app, contract, sender, msg).unwrap_err().downcast().unwrap()
+}
+
+/// The value of an attribute of any event in a response.
+pub fn attribute<'a>(
+    response: &'a AppResponse,
+    key: &str,
+) -> Option<&'a str> {
+    response
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == key)
+        .map(|a| a.value.as_str())
+}
+
+pub fn create(
+    app: &mut App,
+    contract: &Addr,
+    id: u64,
+    data: Value,
+) {
+    let msg = ExecuteMsg::Create(CreateArgs {
+        id: Some(id.into()),
+        data,
+        owner: None,
+        validity: None,
+    });
+    app.execute_contract(Addr::unchecked(OPERATOR), contract.to_owned(), &msg, &[])
+        .unwrap();
+}
+
+pub fn query_json(
+    app: &App,
+    contract: &Addr,
+    msg: Value,
+) -> Value {
+    let msg: QueryMsg = serde_json::from_value(msg).unwrap();
+    app.wrap().query_wasm_smart(contract, &msg).unwrap()
+}
+
+/// Read the given IDs with all their data, returning those that exist.
+pub fn read_ids(
+    app: &App,
+    contract: &Addr,
+    ids: &[u64],
+) -> ReadResponse {
+    let args = ReadArgs {
+        target: ReadTarget::Ids(ids.iter().map(|id| Uint64::new(*id)).collect()),
+        desc: None,
+        select: Some(vec!["*".to_owned()]),
+        filter: None,
+        timestamps: None,
+        include_inactive: None,
+        expand: None,
+    };
+    app.wrap().query_wasm_smart(contract, &QueryMsg::Read(args)).unwrap()
+}
+
+/// IDs returned by a read query.
+pub fn read_ids_of(
+    app: &App,
+    contract: &Addr,
+    args: Value,
+) -> Vec<u64> {
+    let response: ReadResponse = app.wrap().query_wasm_smart(contract, &json!({ "read": args })).unwrap();
+    response.entities.iter().map(|e| e.id.u64()).collect()
+}
+
+/// IDs from every page of a read query, following the cursor of its target.
+pub fn read_all_ids(
+    app: &App,
+    contract: &Addr,
+    args: Value,
+) -> Vec<u64> {
+    let mut args = args;
+    let mut ids = vec![];
+    loop {
+        let response: ReadResponse = app.wrap().query_wasm_smart(contract, &json!({ "read": args })).unwrap();
+        ids.extend(response.entities.iter().map(|e| e.id.u64()));
+        let Some(cursor) = response.cursor else {
+            return ids;
+        };
+        let target = args["target"].as_object_mut().unwrap().values_mut().next().unwrap();
+        target["cursor"] = json!(cursor);
+    }
+}
+
+/// A contract's own storage within the app, for setting up state no message
+/// can produce, such as that left behind by earlier versions.
+pub struct ContractStorage<'a> {
+    storage: &'a mut dyn Storage,
+    prefix: Vec<u8>,
+}
+
+impl<'a> ContractStorage<'a> {
+    pub fn new(
+        app: &'a mut App,
+        contract: &Addr,
+    ) -> Self {
+        // The app nests contract storage under length-prefixed namespaces
+        let mut prefix = vec![];
+        for namespace in [b"wasm".to_vec(), format!("contract_data/{}", contract).into_bytes()] {
+            prefix.extend_from_slice(&(namespace.len() as u16).to_be_bytes());
+            prefix.extend_from_slice(&namespace);
+        }
+        Self {
+            storage: app.storage_mut(),
+            prefix,
+        }
+    }
+
+    fn key(
+        &self,
+        key: &[u8],
+    ) -> Vec<u8> {
+        [self.prefix.as_slice(), key].concat()
+    }
+}
+
+impl Storage for ContractStorage<'_> {
+    fn get(
+        &self,
+        key: &[u8],
+    ) -> Option<Vec<u8>> {
+        self.storage.get(&self.key(key))
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let start = self.key(start.unwrap_or_default());
+        // The prefix ends in the contract address, so its last byte is never 0xff
+        let end = match end {
+            Some(end) => self.key(end),
+            None => {
+                let mut end = self.prefix.to_owned();
+                *end.last_mut().unwrap() += 1;
+                end
+            },
+        };
+        let records: Vec<Record> = self
+            .storage
+            .range(Some(&start), Some(&end), order)
+            .map(|(key, value)| (key[self.prefix.len()..].to_vec(), value))
+            .collect();
+        Box::new(records.into_iter())
+    }
+
+    fn set(
+        &mut self,
+        key: &[u8],
+        value: &[u8],
+    ) {
+        let key = self.key(key);
+        self.storage.set(&key, value)
+    }
+
+    fn remove(
+        &mut self,
+        key: &[u8],
+    ) {
+        let key = self.key(key);
+        self.storage.remove(&key)
+    }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{create, exec, exec_err, read_all_ids, setup, OPERATOR};
+use contract::error::ContractError;
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "tickets", "indexes": [{"name": "queue", "properties": ["status", "priority"]}], "properties": [
+        {"name": "status", "value": {"string": {"max_byte_size": 8}}},
+        {"name": "priority", "value": {"u32": {}}},
+    ]})
+}
+
+fn setup_tickets() -> (App, Addr) {
+    let (mut app, addr) = setup(schema(), None);
+    for (id, status, priority) in [
+        (1, "open", 3),
+        (2, "closed", 1),
+        (3, "open", 1),
+        (4, "open", 2),
+        (5, "open", 9),
+    ] {
+        create(&mut app, &addr, id, json!({"status": status, "priority": priority}));
+    }
+    (app, addr)
+}
+
+#[test]
+fn prefix_and_range_page_in_key_order() {
+    let (app, addr) = setup_tickets();
+    let open = |start: Value, stop: Value, desc: bool| {
+        read_all_ids(
+            &app,
+            &addr,
+            json!({"target": {"compound": {
+                "index": "queue", "prefix": ["open"], "start": start, "stop": stop, "limit": 1,
+            }}, "desc": desc}),
+        )
+    };
+    assert_eq!(open(json!(null), json!(null), false), vec![3, 4, 1, 5]);
+    assert_eq!(open(json!(null), json!(null), true), vec![5, 1, 4, 3]);
+    assert_eq!(
+        open(json!({"inclusive": 2}), json!({"exclusive": 9}), false),
+        vec![4, 1]
+    );
+
+    let all = read_all_ids(
+        &app,
+        &addr,
+        json!({"target": {"compound": {"index": "queue", "prefix": [], "limit": 2}}}),
+    );
+    assert_eq!(all, vec![2, 3, 4, 1, 5]);
+}
+
+#[test]
+fn index_follows_updates_and_deletes() {
+    let (mut app, addr) = setup_tickets();
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "5", "data": {"status": "closed"}, "mode": "merge"}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "3"}})).unwrap();
+    let closed = read_all_ids(
+        &app,
+        &addr,
+        json!({"target": {"compound": {"index": "queue", "prefix": ["closed"]}}}),
+    );
+    assert_eq!(closed, vec![2, 5]);
+    let open = read_all_ids(
+        &app,
+        &addr,
+        json!({"target": {"compound": {"index": "queue", "prefix": ["open"]}}}),
+    );
+    assert_eq!(open, vec![4, 1]);
+}
+
+#[test]
+fn prefix_cannot_outrun_the_index() {
+    let (app, addr) = setup_tickets();
+    let err = app
+        .wrap()
+        .query_wasm_smart::<Value>(
+            &addr,
+            &json!({"read": {"target": {"compound": {"index": "queue", "prefix": ["open", 1, 2]}}}}),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("too many values"));
+}
+
+#[test]
+fn indexed_properties_cannot_be_dropped() {
+    let (mut app, addr) = setup_tickets();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [{"drop_property": {"name": "priority"}}]}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+}
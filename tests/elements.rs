@@ -0,0 +1,62 @@
+mod common;
+
+use common::{create, exec, read_all_ids, setup, OPERATOR};
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "pools", "properties": [
+        {"name": "tags", "indexed": true, "value": {"array": {"items": {"string": {}}, "index_elements": true}}},
+        {"name": "fees", "indexed": true, "value": {"array": {"items": {"u32": {}}, "index_elements": true}}},
+    ]})
+}
+
+fn tagged(
+    app: &App,
+    addr: &Addr,
+    tag: &str,
+) -> Vec<u64> {
+    read_all_ids(
+        app,
+        addr,
+        json!({"target": {"equals": {"property": "tags", "value": tag, "limit": 1}}}),
+    )
+}
+
+#[test]
+fn equals_matches_any_element() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"tags": ["defi", "dex"]}));
+    create(&mut app, &addr, 2, json!({"tags": ["nft"]}));
+    create(&mut app, &addr, 3, json!({"tags": ["defi", "defi", "lending"]}));
+    assert_eq!(tagged(&app, &addr, "defi"), vec![1, 3]);
+    assert_eq!(tagged(&app, &addr, "nft"), vec![2]);
+    assert_eq!(tagged(&app, &addr, "dao"), Vec::<u64>::new());
+}
+
+#[test]
+fn updates_move_only_changed_elements() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"tags": ["defi", "dex"], "fees": [30, 5]}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"tags": ["dex", "dao"]}, "mode": "merge"}}),
+    )
+    .unwrap();
+    assert_eq!(tagged(&app, &addr, "defi"), Vec::<u64>::new());
+    assert_eq!(tagged(&app, &addr, "dex"), vec![1]);
+    assert_eq!(tagged(&app, &addr, "dao"), vec![1]);
+
+    // Element bounds range over the numeric elements
+    let ids = read_all_ids(
+        &app,
+        &addr,
+        json!({"target": {"range": {"property": "fees", "start": {"inclusive": 10}}}}),
+    );
+    assert_eq!(ids, vec![1]);
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    assert_eq!(tagged(&app, &addr, "dex"), Vec::<u64>::new());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{create, instantiate_collection, query_json, setup, store_code};
+use contract::responses::ReadResponse;
+use cw_multi_test::App;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "posts", "properties": [
+        {"name": "title", "value": {"string": {}}},
+        {"name": "author", "value": {"ref": {}}},
+        {"name": "editor", "value": {"ref": {}}},
+        {"name": "parent", "value": {"ref": {}}},
+    ]})
+}
+
+fn read(
+    app: &App,
+    addr: &cosmwasm_std::Addr,
+    args: serde_json::Value,
+) -> ReadResponse {
+    serde_json::from_value(query_json(app, addr, json!({ "read": args }))).unwrap()
+}
+
+#[test]
+fn expand_inlines_referenced_entities() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "alice"}));
+    create(&mut app, &addr, 10, json!({"title": "post", "author": "1"}));
+    create(
+        &mut app,
+        &addr,
+        11,
+        json!({"title": "comment", "author": "1", "parent": "10"}),
+    );
+    let response = query_json(
+        &app,
+        &addr,
+        json!({"read": {
+            "target": {"ids": ["11"]},
+            "select": ["title"],
+            "expand": [
+                {"property": "author"},
+                {"property": "editor"},
+                {"property": "parent", "select": ["title"], "expand": [{"property": "author", "select": []}]},
+            ],
+        }}),
+    );
+    assert_eq!(
+        response["entities"][0]["expanded"],
+        json!({
+            "author": {"id": "1", "owner": null, "data": {"title": "alice"}, "timestamps": null, "expanded": null},
+            "parent": {
+                "id": "10",
+                "owner": null,
+                "data": {"title": "post"},
+                "timestamps": null,
+                "expanded": {
+                    "author": {"id": "1", "owner": null, "data": {}, "timestamps": null, "expanded": null},
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn expand_rejects_non_reference_and_deep_expansion() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "alice"}));
+    let msg = json!({"read": {"target": {"ids": ["1"]}, "expand": [{"property": "title"}]}});
+    assert!(app.wrap().query_wasm_smart::<ReadResponse>(&addr, &msg).is_err());
+    let deep = json!([{"property": "parent", "expand": [{"property": "parent", "expand": [
+        {"property": "parent", "expand": [{"property": "parent"}]},
+    ]}]}]);
+    let msg = json!({"read": {"target": {"ids": ["1"]}, "expand": deep}});
+    assert!(app.wrap().query_wasm_smart::<ReadResponse>(&addr, &msg).is_err());
+}
+
+#[test]
+fn expand_stops_when_budget_runs_out() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "alice"}));
+    let ids: Vec<String> = (10..60).map(|id| id.to_string()).collect();
+    for id in 10..60 {
+        create(
+            &mut app,
+            &addr,
+            id,
+            json!({"title": "post", "author": "1", "editor": "1", "parent": "1"}),
+        );
+    }
+    let response = read(
+        &app,
+        &addr,
+        json!({"target": {"ids": ids}, "expand": [
+            {"property": "author"},
+            {"property": "editor"},
+            {"property": "parent"},
+        ]}),
+    );
+    assert_eq!(response.entities.len(), 50);
+    let n_expanded = response.entities.iter().filter(|e| e.expanded.is_some()).count();
+    assert_eq!(n_expanded, 33);
+    assert!(response.entities[..33]
+        .iter()
+        .all(|e| e.expanded.as_ref().unwrap().len() == 3));
+}
+
+#[test]
+fn expand_reads_other_contracts() {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    let users = instantiate_collection(
+        &mut app,
+        code_id,
+        json!({"name": "users", "properties": [{"name": "name", "value": {"string": {}}}]}),
+        None,
+    );
+    let posts = instantiate_collection(
+        &mut app,
+        code_id,
+        json!({"name": "posts", "properties": [
+            {"name": "author", "value": {"ref": {"contract": users.to_string()}}},
+        ]}),
+        None,
+    );
+    create(&mut app, &users, 1, json!({"name": "alice"}));
+    create(&mut app, &posts, 10, json!({"author": "1"}));
+    let response = read(
+        &app,
+        &posts,
+        json!({"target": {"ids": ["10"]}, "expand": [{"property": "author"}]}),
+    );
+    let author = &response.entities[0].expanded.as_ref().unwrap()["author"];
+    assert_eq!(author.data, Some(json!({"name": "alice"})));
+}
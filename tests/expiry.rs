@@ -0,0 +1,88 @@
+mod common;
+
+use common::{attribute, exec, read_ids_of, setup, OPERATOR};
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "listings", "properties": [{"name": "price", "indexed": true, "value": {"u32": {}}}]})
+}
+
+/// Nanoseconds `secs` seconds from the current block time, as JSON.
+fn from_now(
+    app: &App,
+    secs: u64,
+) -> Value {
+    json!(app.block_info().time.plus_seconds(secs).nanos().to_string())
+}
+
+fn create_valid(
+    app: &mut App,
+    addr: &Addr,
+    id: u64,
+    validity: Value,
+) {
+    let msg = json!({"create": {"id": id.to_string(), "data": {"price": id}, "validity": validity}});
+    exec(app, addr, OPERATOR, msg).unwrap();
+}
+
+fn by_price(
+    app: &App,
+    addr: &Addr,
+    include_inactive: bool,
+) -> Vec<u64> {
+    read_ids_of(
+        app,
+        addr,
+        json!({"target": {"range": {"property": "price"}}, "include_inactive": include_inactive}),
+    )
+}
+
+#[test]
+fn reads_skip_entities_outside_their_window() {
+    let (mut app, addr) = setup(schema(), None);
+    create_valid(&mut app, &addr, 1, json!({}));
+    let later = from_now(&app, 100);
+    create_valid(&mut app, &addr, 2, json!({"valid_from": later}));
+    let soon = from_now(&app, 10);
+    create_valid(&mut app, &addr, 3, json!({"expires_at": soon}));
+    assert_eq!(by_price(&app, &addr, false), vec![1, 3]);
+
+    app.update_block(|block| block.time = block.time.plus_seconds(50));
+    assert_eq!(by_price(&app, &addr, false), vec![1]);
+    assert_eq!(by_price(&app, &addr, true), vec![1, 2, 3]);
+    app.update_block(|block| block.time = block.time.plus_seconds(50));
+    assert_eq!(by_price(&app, &addr, false), vec![1, 2]);
+
+    // A new window brings it back
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "3", "data": {}, "mode": "merge", "validity": {}}}),
+    )
+    .unwrap();
+    assert_eq!(by_price(&app, &addr, false), vec![1, 2, 3]);
+}
+
+#[test]
+fn anyone_can_prune_expired_entities_in_batches() {
+    let (mut app, addr) = setup(schema(), None);
+    for id in 1..=3 {
+        let expires_at = from_now(&app, id * 10);
+        create_valid(&mut app, &addr, id, json!({"expires_at": expires_at}));
+    }
+    create_valid(&mut app, &addr, 4, json!({}));
+    app.update_block(|block| block.time = block.time.plus_seconds(25));
+
+    let response = exec(&mut app, &addr, "anyone", json!({"prune": {"limit": 1}})).unwrap();
+    assert_eq!(attribute(&response, "pruned"), Some("1"));
+    assert_eq!(attribute(&response, "done"), Some("false"));
+    let response = exec(&mut app, &addr, "anyone", json!({"prune": {"limit": 1}})).unwrap();
+    assert_eq!(attribute(&response, "pruned"), Some("1"));
+    assert_eq!(attribute(&response, "done"), Some("true"));
+
+    // Gone from the index too, not merely hidden
+    assert_eq!(by_price(&app, &addr, true), vec![3, 4]);
+}
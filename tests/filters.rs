@@ -0,0 +1,139 @@
+mod common;
+
+use common::{create, exec, read_ids_of, setup, OPERATOR};
+use contract::responses::ReadResponse;
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "listings", "properties": [
+        {"name": "price", "indexed": true, "value": {"u32": {}}},
+        {"name": "title", "value": {"string": {}}},
+        {"name": "tags", "value": {"array": {"items": {"string": {}}}}},
+        {"name": "note", "value": {"string": {}}},
+    ]})
+}
+
+fn setup_listings() -> (App, Addr) {
+    let (mut app, addr) = setup(schema(), None);
+    create(
+        &mut app,
+        &addr,
+        1,
+        json!({"price": 10, "title": "red bike", "tags": ["bike"], "note": "new"}),
+    );
+    create(
+        &mut app,
+        &addr,
+        2,
+        json!({"price": 20, "title": "blue bike", "tags": ["bike", "sale"]}),
+    );
+    create(
+        &mut app,
+        &addr,
+        3,
+        json!({"price": 30, "title": "red car", "tags": ["car"]}),
+    );
+    create(&mut app, &addr, 4, json!({"price": 40, "title": "lamp", "tags": []}));
+    (app, addr)
+}
+
+fn filtered(
+    app: &App,
+    addr: &Addr,
+    filter: Value,
+) -> Vec<u64> {
+    read_ids_of(
+        app,
+        addr,
+        json!({"target": {"range": {"property": "price"}}, "filter": filter}),
+    )
+}
+
+#[test]
+fn comparisons() {
+    let (app, addr) = setup_listings();
+    assert_eq!(
+        filtered(&app, &addr, json!({"eq": {"property": "price", "value": 20}})),
+        vec![2]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"ne": {"property": "price", "value": 20}})),
+        vec![1, 3, 4]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"lt": {"property": "price", "value": 20}})),
+        vec![1]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"lte": {"property": "price", "value": 20}})),
+        vec![1, 2]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"gt": {"property": "price", "value": 30}})),
+        vec![4]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"gte": {"property": "price", "value": 30}})),
+        vec![3, 4]
+    );
+    assert_eq!(
+        filtered(
+            &app,
+            &addr,
+            json!({"in": {"property": "price", "values": [10, 40, 50]}})
+        ),
+        vec![1, 4]
+    );
+    assert_eq!(filtered(&app, &addr, json!({"exists": {"property": "note"}})), vec![1]);
+    assert_eq!(
+        filtered(&app, &addr, json!({"contains": {"property": "title", "value": "bike"}})),
+        vec![1, 2]
+    );
+    assert_eq!(
+        filtered(&app, &addr, json!({"contains": {"property": "tags", "value": "sale"}})),
+        vec![2]
+    );
+}
+
+#[test]
+fn combinators() {
+    let (app, addr) = setup_listings();
+    let red = json!({"contains": {"property": "title", "value": "red"}});
+    let cheap = json!({"lt": {"property": "price", "value": 25}});
+    assert_eq!(filtered(&app, &addr, json!({"and": [red, cheap]})), vec![1]);
+    assert_eq!(filtered(&app, &addr, json!({"or": [red, cheap]})), vec![1, 2, 3]);
+    assert_eq!(filtered(&app, &addr, json!({"not": {"or": [red, cheap]}})), vec![4]);
+    // A comparison against a missing property is false
+    assert_eq!(
+        filtered(
+            &app,
+            &addr,
+            json!({"not": {"eq": {"property": "note", "value": "new"}}})
+        ),
+        vec![2, 3, 4]
+    );
+}
+
+#[test]
+fn exhausted_scan_returns_a_cursor() {
+    let (mut app, addr) = setup(schema(), None);
+    let ops: Vec<Value> = (1..=600)
+        .map(|id| json!({"create": {"id": id.to_string(), "data": {"price": id}}}))
+        .collect();
+    exec(&mut app, &addr, OPERATOR, json!({ "batch": ops })).unwrap();
+
+    let mut args = json!({
+        "target": {"range": {"property": "price"}},
+        "filter": {"gt": {"property": "price", "value": 590}},
+    });
+    let response: ReadResponse = app.wrap().query_wasm_smart(&addr, &json!({ "read": args })).unwrap();
+    assert!(response.entities.is_empty());
+    let cursor = response.cursor.expect("a cursor to resume from");
+
+    args["target"]["range"]["cursor"] = json!(cursor);
+    let response: ReadResponse = app.wrap().query_wasm_smart(&addr, &json!({ "read": args })).unwrap();
+    let ids: Vec<u64> = response.entities.iter().map(|e| e.id.u64()).collect();
+    assert_eq!(ids, (591..=600).collect::<Vec<_>>());
+}
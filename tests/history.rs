@@ -0,0 +1,111 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids, setup, OPERATOR};
+use contract::{error::ContractError, responses::HistoryResponse};
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "docs", "properties": [{"name": "v", "value": {"u32": {}}}]})
+}
+
+fn set_v(
+    app: &mut App,
+    addr: &Addr,
+    v: u32,
+) {
+    exec(
+        app,
+        addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"v": v}, "mode": "merge"}}),
+    )
+    .unwrap();
+}
+
+fn history(
+    app: &App,
+    addr: &Addr,
+    cursor: Option<u32>,
+    limit: u8,
+) -> HistoryResponse {
+    let msg = json!({"history": {"id": "1", "cursor": cursor, "limit": limit}});
+    app.wrap().query_wasm_smart(addr, &msg).unwrap()
+}
+
+#[test]
+fn history_keeps_recent_revisions_with_diffs() {
+    let (mut app, addr) = setup(schema(), Some(json!({"history_depth": 2})));
+    create(&mut app, &addr, 1, json!({"v": 1}));
+    for v in 2..=4 {
+        set_v(&mut app, &addr, v);
+    }
+
+    let page = history(&app, &addr, None, 10);
+    assert_eq!(page.revision, 4);
+    let revisions: Vec<u32> = page.revisions.iter().map(|r| r.revision).collect();
+    assert_eq!(revisions, vec![4, 3, 2]);
+    let change = &page.revisions[0].changes.as_ref().unwrap()[0];
+    assert_eq!(
+        (change.old.to_owned(), change.new.to_owned()),
+        (Some(json!(3)), Some(json!(4)))
+    );
+    // Revision 1 is gone, so there is nothing to compare revision 2 with
+    assert_eq!(page.revisions[2].changes, None);
+    assert_eq!(page.cursor, None);
+
+    let page = history(&app, &addr, None, 2);
+    assert_eq!(page.cursor, Some(3));
+    let page = history(&app, &addr, page.cursor, 2);
+    let revisions: Vec<u32> = page.revisions.iter().map(|r| r.revision).collect();
+    assert_eq!(revisions, vec![2]);
+}
+
+#[test]
+fn expected_revision_guards_writes() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"v": 1}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"v": 2}, "mode": "merge", "expected_revision": 1}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"v": 3}, "mode": "merge", "expected_revision": 1}}),
+    );
+    assert!(matches!(err, ContractError::RevisionMismatch { .. }));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"delete": {"id": "1", "expected_revision": 1}}),
+    );
+    assert!(matches!(err, ContractError::RevisionMismatch { .. }));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"delete": {"id": "1", "expected_revision": 2}}),
+    )
+    .unwrap();
+}
+
+#[test]
+fn revert_writes_a_new_revision() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"v": 1}));
+    set_v(&mut app, &addr, 2);
+    set_v(&mut app, &addr, 3);
+    exec(&mut app, &addr, OPERATOR, json!({"revert": {"id": "1", "revision": 1}})).unwrap();
+    assert_eq!(read_ids(&app, &addr, &[1]).entities[0].data, Some(json!({"v": 1})));
+    assert_eq!(history(&app, &addr, None, 1).revision, 4);
+
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"revert": {"id": "1", "revision": 9}}));
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{create, exec, exec_err, setup, OPERATOR};
+use contract::{error::ContractError, responses::CreateResponse};
+use cosmwasm_std::{from_json, Addr};
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "notes", "properties": [{"name": "text", "value": {"string": {}}}]})
+}
+
+/// Create an entity without an ID, returning the one it was given.
+fn create_auto(
+    app: &mut App,
+    addr: &Addr,
+    data: Value,
+) -> u64 {
+    let response = exec(app, addr, OPERATOR, json!({"create": {"data": data}})).unwrap();
+    let CreateResponse { id } = from_json(response.data.unwrap()).unwrap();
+    id.u64()
+}
+
+#[test]
+fn caller_supplies_unused_ids() {
+    let (mut app, addr) = setup(schema(), None);
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"create": {"data": {"text": "a"}}}));
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+    create(&mut app, &addr, 7, json!({"text": "a"}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "7", "data": {"text": "b"}}}),
+    );
+    assert!(matches!(err, ContractError::AlreadyExists { .. }));
+}
+
+#[test]
+fn sequence_assigns_ascending_ids() {
+    let (mut app, addr) = setup(schema(), Some(json!({"id_strategy": "sequence"})));
+    assert_eq!(create_auto(&mut app, &addr, json!({"text": "a"})), 1);
+    assert_eq!(create_auto(&mut app, &addr, json!({"text": "a"})), 2);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "9", "data": {"text": "b"}}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+}
+
+#[test]
+fn content_hash_derives_ids_from_data() {
+    let (mut app, addr) = setup(schema(), Some(json!({"id_strategy": "content_hash"})));
+    let a = create_auto(&mut app, &addr, json!({"text": "a"}));
+    let b = create_auto(&mut app, &addr, json!({"text": "b"}));
+    assert_ne!(a, b);
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"create": {"data": {"text": "a"}}}));
+    assert!(matches!(err, ContractError::AlreadyExists { .. }));
+
+    // Another collection derives the same ID from the same data
+    let (mut app, addr) = setup(schema(), Some(json!({"id_strategy": "content_hash"})));
+    assert_eq!(create_auto(&mut app, &addr, json!({"text": "a"})), a);
+}
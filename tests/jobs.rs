@@ -0,0 +1,123 @@
+mod common;
+
+use common::{attribute, create, exec, exec_err, query_json, read_ids_of, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::json;
+
+#[test]
+fn job_runs_in_batches() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "n", "value": {"u32": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    for id in 1..=5 {
+        create(&mut app, &addr, id, json!({"n": id}));
+    }
+    exec(&mut app, &addr, OPERATOR, json!({"rebuild_index": {"property": "n"}})).unwrap_err();
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [{"set_indexed": {"name": "n", "indexed": true}}]}}),
+    )
+    .unwrap();
+    let job = query_json(&app, &addr, json!({"job": {}}));
+    assert_eq!(job["incomplete_indices"], json!(["_ix_n"]));
+    assert_eq!(job["job"]["total"], json!(5));
+
+    let response = exec(&mut app, &addr, "anyone", json!({"continue_job": {"limit": 3}})).unwrap();
+    assert_eq!(attribute(&response, "done"), Some("false"));
+    let err = app
+        .wrap()
+        .query_wasm_smart::<serde_json::Value>(&addr, &json!({"read": {"target": {"range": {"property": "n"}}}}))
+        .unwrap_err();
+    assert!(err.to_string().contains("being rebuilt"));
+    let response = exec(&mut app, &addr, "anyone", json!({"continue_job": {"limit": 3}})).unwrap();
+    assert_eq!(attribute(&response, "done"), Some("true"));
+    assert_eq!(query_json(&app, &addr, json!({"job": {}})), json!(null));
+
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"range": {"property": "n"}}, "desc": true}),
+    );
+    assert_eq!(ids, vec![5, 4, 3, 2, 1]);
+    let err = exec_err(&mut app, &addr, "anyone", json!({"continue_job": {}}));
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
+
+#[test]
+fn rebuild_index_leaves_unique_writes_alone() {
+    let schema = json!({"name": "users", "properties": [
+        {"name": "email", "indexed": true, "unique": true, "value": {"string": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    create(&mut app, &addr, 1, json!({"email": "a@x"}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"rebuild_index": {"property": "email"}}),
+    )
+    .unwrap();
+
+    create(&mut app, &addr, 2, json!({"email": "b@x"}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "3", "data": {"email": "a@x"}}}),
+    );
+    assert!(matches!(err, ContractError::AlreadyExists { .. }));
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"unique": {"property": "email", "value": "b@x"}}}),
+    );
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn job_skips_values_it_cannot_index() {
+    let schema = json!({"name": "things", "properties": [
+        {"name": "a", "value": {"string": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    // Neither is declared yet, so any value goes
+    create(&mut app, &addr, 1, json!({"a": "x", "t": 5, "n": 300}));
+    create(&mut app, &addr, 2, json!({"a": "y", "t": "hello world", "n": 3}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [
+            {"add_property": {"name": "t", "searchable": true, "value": {"string": {}}}},
+            {"add_property": {"name": "n", "indexed": true, "value": {"u8": {}}}},
+        ]}}),
+    )
+    .unwrap();
+
+    let response = exec(&mut app, &addr, "anyone", json!({"continue_job": {}})).unwrap();
+    assert_eq!(attribute(&response, "skipped"), Some("1"));
+    assert_eq!(attribute(&response, "done"), Some("true"));
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"search": {"property": "t", "terms": ["hello"]}}}),
+    );
+    assert_eq!(ids, vec![2]);
+    let ids = read_ids_of(&app, &addr, json!({"target": {"range": {"property": "n"}}}));
+    assert_eq!(ids, vec![2]);
+
+    // The entity can still be fixed up or deleted
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"t": "hello", "n": 4}, "mode": "merge"}}),
+    )
+    .unwrap();
+    let ids = read_ids_of(&app, &addr, json!({"target": {"range": {"property": "n"}}}));
+    assert_eq!(ids, vec![2, 1]);
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{attribute, create, exec, setup, ContractStorage, OPERATOR};
+use contract::{
+    msg::MigrateMsg,
+    responses::ReadResponse,
+    state::{PropertyIndex, ENTITY, INDEX_ENCODING},
+};
+use cosmwasm_std::{Addr, Binary, Order, StdResult};
+use cw_multi_test::{App, AppResponse, Executor};
+use serde_json::json;
+
+fn migrate(
+    app: &mut App,
+    contract: &Addr,
+) -> AppResponse {
+    let code_id = app.contract_data(contract).unwrap().code_id;
+    app.migrate_contract(Addr::unchecked(OPERATOR), contract.to_owned(), &MigrateMsg {}, code_id)
+        .unwrap()
+}
+
+/// Page through the `n` index two entities at a time.
+fn read_range(
+    app: &App,
+    contract: &Addr,
+    desc: bool,
+) -> StdResult<Vec<u64>> {
+    let mut ids = vec![];
+    let mut cursor: Option<Binary> = None;
+    loop {
+        let msg = json!({"read": {
+            "target": {"range": {"property": "n", "cursor": cursor, "limit": 2}},
+            "desc": desc,
+        }});
+        let response: ReadResponse = app.wrap().query_wasm_smart(contract, &msg)?;
+        ids.extend(response.entities.iter().map(|e| e.id.u64()));
+        match response.cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(ids),
+        }
+    }
+}
+
+#[test]
+fn migrate_rebuilds_legacy_index_keys() {
+    let schema = json!({"name": "x", "properties": [{"name": "n", "indexed": true, "value": {"i64": {}}}]});
+    let (mut app, addr) = setup(schema, None);
+    let values = [(1, 300i64), (2, -5), (3, 3), (4, -1)];
+    for (id, n) in values {
+        create(&mut app, &addr, id, json!({"n": n}));
+    }
+    assert_eq!(read_range(&app, &addr, false).unwrap(), vec![2, 4, 3, 1]);
+
+    // Put the index back the way versions before the encoding change wrote
+    // it: little-endian, with negative numbers clamped to zero
+    let index = PropertyIndex::new("_ix_n");
+    let mut storage = ContractStorage::new(&mut app, &addr);
+    let keys = index
+        .keys(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    for (key, id) in keys {
+        index.remove(&mut storage, (&key, id));
+    }
+    for (id, n) in values {
+        index.save(&mut storage, (&n.max(0).to_le_bytes(), id), &1).unwrap();
+    }
+    INDEX_ENCODING.remove(&mut storage);
+
+    let response = migrate(&mut app, &addr);
+    assert_eq!(attribute(&response, "job_queued"), Some("true"));
+    assert!(read_range(&app, &addr, false).is_err());
+    loop {
+        let response = exec(&mut app, &addr, OPERATOR, json!({"continue_job": {"limit": 3}})).unwrap();
+        if attribute(&response, "done") == Some("true") {
+            break;
+        }
+    }
+    assert_eq!(read_range(&app, &addr, false).unwrap(), vec![2, 4, 3, 1]);
+    assert_eq!(read_range(&app, &addr, true).unwrap(), vec![1, 3, 4, 2]);
+    let storage = ContractStorage::new(&mut app, &addr);
+    assert_eq!(index.keys(&storage, None, None, Order::Ascending).count(), values.len());
+
+    let response = migrate(&mut app, &addr);
+    assert_eq!(attribute(&response, "job_queued"), Some("false"));
+}
+
+#[test]
+fn migrate_skips_legacy_values_that_cannot_be_indexed() {
+    let schema = json!({"name": "x", "properties": [{"name": "n", "indexed": true, "value": {"u8": {}}}]});
+    let (mut app, addr) = setup(schema, None);
+    for id in 1..=3 {
+        create(&mut app, &addr, id, json!({"n": id}));
+    }
+    // Versions before values were range checked stored this as is
+    let mut storage = ContractStorage::new(&mut app, &addr);
+    ENTITY.save(&mut storage, 2, &json!({"n": 300})).unwrap();
+    INDEX_ENCODING.remove(&mut storage);
+
+    migrate(&mut app, &addr);
+    let response = exec(&mut app, &addr, OPERATOR, json!({"continue_job": {"limit": 10}})).unwrap();
+    assert_eq!(attribute(&response, "skipped"), Some("2"));
+    assert_eq!(attribute(&response, "done"), Some("true"));
+    assert_eq!(read_range(&app, &addr, false).unwrap(), vec![1, 3]);
+
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "2", "data": {"n": 2}, "mode": "replace"}}),
+    )
+    .unwrap();
+    assert_eq!(read_range(&app, &addr, false).unwrap(), vec![1, 2, 3]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{create, exec_err, query_json, read_ids_of, setup, store_code, OPERATOR};
+use contract::{error::ContractError, msg::InstantiateMsg, schema::EntitySchema};
+use cosmwasm_std::Addr;
+use cw_multi_test::{App, Executor};
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "users", "properties": [
+        {"name": "profile", "value": {"object": {"properties": [
+            {"name": "handle", "unique": true, "value": {"string": {}}},
+            {"name": "country", "indexed": true, "value": {"string": {}}},
+            {"name": "age", "value": {"u8": {}}},
+        ]}}},
+        {"name": "meta", "value": {"object": {"properties": [
+            {"name": "author", "value": {"ref": {}}},
+        ]}}},
+    ]})
+}
+
+#[test]
+fn nested_fields_are_validated_indexed_and_selected() {
+    let (mut app, addr) = setup(schema(), None);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "1", "data": {"profile": {"age": 300}}}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+    create(
+        &mut app,
+        &addr,
+        1,
+        json!({"profile": {"handle": "a", "country": "fr", "age": 30}}),
+    );
+    create(&mut app, &addr, 2, json!({"profile": {"handle": "b", "country": "de"}}));
+    create(&mut app, &addr, 3, json!({"profile": {"handle": "c", "country": "fr"}}));
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"equals": {"property": "profile.country", "value": "fr"}}}),
+    );
+    assert_eq!(ids, vec![1, 3]);
+    let response = query_json(
+        &app,
+        &addr,
+        json!({"read": {"target": {"ids": ["1"]}, "select": ["profile.age"]}}),
+    );
+    assert_eq!(response["entities"][0]["data"], json!({"profile": {"age": 30}}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "4", "data": {"profile": {"handle": "a"}}}}),
+    );
+    assert!(matches!(
+        err,
+        ContractError::AlreadyExists { .. } | ContractError::ValidationError { .. }
+    ));
+}
+
+#[test]
+fn dotted_keys_cannot_shadow_nested_fields() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"profile": {"handle": "alice"}}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "2", "data": {"profile": {"handle": "alice"}, "profile.handle": "bob"}}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "3", "data": {"meta": {"author": "999"}, "meta.author": "1"}}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "3", "data": {"meta": {"author": "999"}}}}),
+    );
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
+
+#[test]
+fn property_names_cannot_contain_dots() {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    let schema: EntitySchema = serde_json::from_value(json!({"name": "x", "properties": [
+        {"name": "a", "value": {"object": {"properties": [{"name": "b.c", "value": {"u8": {}}}]}}},
+    ]}))
+    .unwrap();
+    let msg = InstantiateMsg {
+        schema,
+        operator: None,
+        metadata: None,
+        config: None,
+    };
+    let result = app.instantiate_contract(code_id, Addr::unchecked(OPERATOR), &msg, &[], "x", None);
+    assert!(result.is_err());
+}
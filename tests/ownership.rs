@@ -0,0 +1,126 @@
+mod common;
+
+use common::{exec, exec_err, query_json, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "listings", "properties": [{"name": "title", "value": {"string": {}}}]})
+}
+
+fn update(title: &str) -> serde_json::Value {
+    json!({"update": {"id": "1", "data": {"title": title}, "mode": "merge"}})
+}
+
+#[test]
+fn creator_owns_entity_with_public_create() {
+    let (mut app, addr) = setup(schema(), Some(json!({"public_create": true})));
+    exec(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "1", "data": {"title": "a"}}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "alice", update("b")).unwrap();
+    let err = exec_err(&mut app, &addr, "bob", update("c"));
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    let err = exec_err(&mut app, &addr, "bob", json!({"delete": {"id": "1"}}));
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    exec(&mut app, &addr, "alice", json!({"delete": {"id": "1"}})).unwrap();
+}
+
+#[test]
+fn public_create_cannot_assign_other_owner() {
+    let (mut app, addr) = setup(schema(), Some(json!({"public_create": true})));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "1", "data": {"title": "a"}, "owner": "bob"}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+}
+
+#[test]
+fn create_requires_writer_without_public_create() {
+    let (mut app, addr) = setup(schema(), None);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "1", "data": {"title": "a"}}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "1", "data": {"title": "a"}, "owner": "alice"}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "alice", update("b")).unwrap();
+}
+
+#[test]
+fn transfer_moves_ownership() {
+    let (mut app, addr) = setup(schema(), Some(json!({"public_create": true})));
+    exec(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "1", "data": {"title": "a"}}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "bob",
+        json!({"transfer_ownership": {"id": "1", "owner": "bob"}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    exec(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"transfer_ownership": {"id": "1", "owner": "bob"}}),
+    )
+    .unwrap();
+    let err = exec_err(&mut app, &addr, "alice", update("b"));
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    exec(&mut app, &addr, "bob", update("b")).unwrap();
+}
+
+#[test]
+fn owner_target_lists_owned_entities_with_owner() {
+    let (mut app, addr) = setup(schema(), Some(json!({"public_create": true})));
+    exec(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "1", "data": {"title": "a"}}}),
+    )
+    .unwrap();
+    exec(
+        &mut app,
+        &addr,
+        "bob",
+        json!({"create": {"id": "2", "data": {"title": "b"}}}),
+    )
+    .unwrap();
+    exec(
+        &mut app,
+        &addr,
+        "alice",
+        json!({"create": {"id": "3", "data": {"title": "c"}}}),
+    )
+    .unwrap();
+    let response = query_json(&app, &addr, json!({"read": {"target": {"owner": {"owner": "alice"}}}}));
+    assert_eq!(
+        response["entities"],
+        json!([
+            {"id": "1", "owner": "alice", "data": null, "timestamps": null, "expanded": null},
+            {"id": "3", "owner": "alice", "data": null, "timestamps": null, "expanded": null},
+        ])
+    );
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::{create, exec, exec_err, instantiate_collection, read_ids, read_ids_of, setup, store_code, OPERATOR};
+use contract::error::ContractError;
+use cw_multi_test::App;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "posts", "properties": [
+        {"name": "title", "value": {"string": {}}},
+        {"name": "author", "value": {"ref": {}}},
+        {"name": "parent", "value": {"ref": {"on_delete": "cascade"}}},
+        {"name": "meta", "value": {"object": {"properties": [
+            {"name": "reviewer", "value": {"ref": {"on_delete": "set_null"}}},
+        ]}}},
+    ]})
+}
+
+#[test]
+fn reference_must_exist() {
+    let (mut app, addr) = setup(schema(), None);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "10", "data": {"title": "post", "author": "1"}}}),
+    );
+    assert!(matches!(err, ContractError::NotFound { .. }));
+    create(&mut app, &addr, 1, json!({"title": "user"}));
+    create(&mut app, &addr, 10, json!({"title": "post", "author": "1"}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "10", "data": {"author": "2"}, "mode": "merge"}}),
+    );
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
+
+#[test]
+fn restrict_blocks_delete_until_unreferenced() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "user a"}));
+    create(&mut app, &addr, 2, json!({"title": "user b"}));
+    create(&mut app, &addr, 10, json!({"title": "post", "author": "1"}));
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}}));
+    assert!(matches!(err, ContractError::Referenced { .. }));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "10", "data": {"author": "2"}, "mode": "merge"}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+}
+
+#[test]
+fn cascade_deletes_referrers() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 10, json!({"title": "post"}));
+    create(&mut app, &addr, 11, json!({"title": "comment", "parent": "10"}));
+    create(&mut app, &addr, 12, json!({"title": "reply", "parent": "11"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "10"}})).unwrap();
+    assert!(read_ids(&app, &addr, &[10, 11, 12]).entities.is_empty());
+}
+
+#[test]
+fn set_null_removes_reference() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 2, json!({"title": "reviewer"}));
+    create(&mut app, &addr, 10, json!({"title": "post", "meta": {"reviewer": "2"}}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "2"}})).unwrap();
+    let entities = read_ids(&app, &addr, &[10]).entities;
+    assert_eq!(entities[0].data, Some(json!({"title": "post", "meta": {}})));
+}
+
+#[test]
+fn self_reference_does_not_block_delete() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "self", "author": "1"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    assert!(read_ids(&app, &addr, &[1]).entities.is_empty());
+}
+
+#[test]
+fn referencing_target_lists_referrers() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "user"}));
+    create(&mut app, &addr, 10, json!({"title": "a", "author": "1"}));
+    create(&mut app, &addr, 11, json!({"title": "b"}));
+    create(&mut app, &addr, 12, json!({"title": "c", "author": "1"}));
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"referencing": {"property": "author", "id": "1"}}}),
+    );
+    assert_eq!(ids, vec![10, 12]);
+}
+
+#[test]
+fn cross_contract_reference_must_exist() {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    let users = instantiate_collection(
+        &mut app,
+        code_id,
+        json!({"name": "users", "properties": [{"name": "name", "value": {"string": {}}}]}),
+        None,
+    );
+    let posts = instantiate_collection(
+        &mut app,
+        code_id,
+        json!({"name": "posts", "properties": [
+            {"name": "author", "value": {"ref": {"contract": users.to_string()}}},
+        ]}),
+        None,
+    );
+    create(&mut app, &users, 1, json!({"name": "alice"}));
+    create(&mut app, &posts, 10, json!({"author": "1"}));
+    let err = exec_err(
+        &mut app,
+        &posts,
+        OPERATOR,
+        json!({"create": {"id": "11", "data": {"author": "2"}}}),
+    );
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
+
+#[test]
+fn prune_skips_entities_with_too_many_referrers() {
+    let (mut app, addr) = setup(schema(), None);
+    let expires_at = app.block_info().time.plus_seconds(10);
+    for (id, parent) in [(1, None), (2, None), (10, Some("1"))] {
+        let data = match parent {
+            Some(parent) => json!({"title": "post", "parent": parent}),
+            None => json!({"title": "post"}),
+        };
+        exec(
+            &mut app,
+            &addr,
+            OPERATOR,
+            json!({"create": {"id": id.to_string(), "data": data, "validity": {"expires_at": expires_at}}}),
+        )
+        .unwrap();
+    }
+    // Entity 1 cascades to entity 10, which has too many replies to cascade
+    // to in turn
+    for id in 100..131 {
+        create(&mut app, &addr, id, json!({"title": "reply", "parent": "10"}));
+    }
+    app.update_block(|block| block.time = block.time.plus_seconds(20));
+    exec(&mut app, &addr, "anyone", json!({"prune": {}})).unwrap();
+    let live = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"ids": ["1", "2", "10"]}, "filter": {"and": []}, "include_inactive": true}),
+    );
+    assert_eq!(live, vec![1, 10]);
+    // The skipped entities no longer hold up later prunes
+    exec(&mut app, &addr, "anyone", json!({"prune": {}})).unwrap();
+}
+
+#[test]
+fn rename_moves_reverse_index() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "user"}));
+    create(&mut app, &addr, 10, json!({"title": "post", "author": "1"}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [{"rename_property": {"name": "author", "new_name": "writer"}}]}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "anyone", json!({"continue_job": {}})).unwrap();
+    let ids = read_ids_of(
+        &app,
+        &addr,
+        json!({"target": {"referencing": {"property": "writer", "id": "1"}}}),
+    );
+    assert_eq!(ids, vec![10]);
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}}));
+    assert!(matches!(err, ContractError::Referenced { .. }));
+}
+
+#[test]
+fn nested_reference_blocks_drop_and_rename() {
+    let (mut app, addr) = setup(schema(), None);
+    for op in [
+        json!({"drop_property": {"name": "meta"}}),
+        json!({"rename_property": {"name": "meta", "new_name": "info"}}),
+    ] {
+        let err = exec_err(&mut app, &addr, OPERATOR, json!({"alter_schema": {"operations": [op]}}));
+        assert!(matches!(err, ContractError::ValidationError { .. }));
+    }
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{exec, exec_err, query_json, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "roles", "properties": [{"name": "title", "value": {"string": {}}}]})
+}
+
+#[test]
+fn writer_can_create_until_quota_runs_out() {
+    let (mut app, addr) = setup(schema(), None);
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "writer", "roles": ["writer"], "quota": 1}}),
+    )
+    .unwrap();
+    exec(
+        &mut app,
+        &addr,
+        "writer",
+        json!({"create": {"id": "1", "data": {"title": "a"}}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "writer",
+        json!({"create": {"id": "2", "data": {"title": "b"}}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    let err = exec_err(&mut app, &addr, "writer", json!({"delete": {"id": "1"}}));
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+}
+
+#[test]
+fn only_operator_grants_admin() {
+    let (mut app, addr) = setup(schema(), None);
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "admin", "roles": ["admin"]}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"grant_roles": {"address": "other", "roles": ["admin"]}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    exec(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"grant_roles": {"address": "other", "roles": ["deleter"]}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "admin", json!({"revoke_roles": {"address": "other"}})).unwrap();
+}
+
+#[test]
+fn admin_cannot_extend_own_grant() {
+    let (mut app, addr) = setup(schema(), None);
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "admin", "roles": ["admin"], "expires": {"at_height": 1000000}, "quota": 5}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"grant_roles": {"address": "admin", "roles": ["writer"]}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    let permissions = query_json(&app, &addr, json!({"permissions": {"address": "admin"}}));
+    assert_eq!(permissions["grant"]["quota"], json!(5));
+    assert_eq!(permissions["grant"]["expires"], json!({"at_height": 1000000}));
+}
+
+#[test]
+fn admin_cannot_change_other_admin() {
+    let (mut app, addr) = setup(schema(), None);
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "admin", "roles": ["admin"]}}),
+    )
+    .unwrap();
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "other", "roles": ["admin"], "quota": 5}}),
+    )
+    .unwrap();
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"grant_roles": {"address": "other", "roles": ["writer"]}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"revoke_roles": {"address": "other", "roles": ["writer"]}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    let permissions = query_json(&app, &addr, json!({"permissions": {"address": "other"}}));
+    assert_eq!(permissions["grant"]["quota"], json!(5));
+}
+
+#[test]
+fn expired_admin_cannot_manage_roles() {
+    let (mut app, addr) = setup(schema(), None);
+    let height = app.block_info().height;
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"grant_roles": {"address": "admin", "roles": ["admin"], "expires": {"at_height": height + 1}}}),
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        "admin",
+        json!({"grant_roles": {"address": "other", "roles": ["writer"]}}),
+    );
+    assert!(matches!(err, ContractError::NotAuthorized { .. }));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids_of, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "docs", "properties": [
+        {"name": "title", "searchable": true, "value": {"string": {}}},
+        {"name": "obj", "value": {"object": {"properties": [
+            {"name": "body", "searchable": true, "value": {"string": {}}},
+        ]}}},
+    ]})
+}
+
+fn search(
+    app: &cw_multi_test::App,
+    addr: &cosmwasm_std::Addr,
+    property: &str,
+    terms: &[&str],
+    match_all: bool,
+) -> Vec<u64> {
+    read_ids_of(
+        app,
+        addr,
+        json!({"target": {"search": {"property": property, "terms": terms, "match_all": match_all}}}),
+    )
+}
+
+#[test]
+fn search_matches_any_or_all_terms() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "The quick brown fox"}));
+    create(&mut app, &addr, 2, json!({"title": "A quick reply"}));
+    create(
+        &mut app,
+        &addr,
+        3,
+        json!({"title": "Brown bread", "obj": {"body": "Fox tales"}}),
+    );
+    assert_eq!(search(&app, &addr, "title", &["QUICK", "brown"], false), vec![1, 2, 3]);
+    assert_eq!(search(&app, &addr, "title", &["quick", "brown"], true), vec![1]);
+    assert_eq!(search(&app, &addr, "obj.body", &["fox"], false), vec![3]);
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"title": "slow fox"}, "mode": "merge"}}),
+    )
+    .unwrap();
+    assert_eq!(search(&app, &addr, "title", &["quick"], false), vec![2]);
+}
+
+#[test]
+fn drop_property_clears_search_index() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"title": "quick fox"}));
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [{"drop_property": {"name": "title"}}]}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "anyone", json!({"continue_job": {}})).unwrap();
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [
+            {"add_property": {"name": "title", "searchable": true, "value": {"string": {}}}},
+        ]}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, "anyone", json!({"continue_job": {}})).unwrap();
+    assert!(search(&app, &addr, "title", &["quick"], false).is_empty());
+}
+
+#[test]
+fn nested_searchable_field_blocks_drop_and_rename() {
+    let (mut app, addr) = setup(schema(), None);
+    for op in [
+        json!({"drop_property": {"name": "obj"}}),
+        json!({"rename_property": {"name": "obj", "new_name": "doc"}}),
+    ] {
+        let err = exec_err(&mut app, &addr, OPERATOR, json!({"alter_schema": {"operations": [op]}}));
+        assert!(matches!(err, ContractError::ValidationError { .. }));
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids_of, setup, store_code, OPERATOR};
+use contract::{error::ContractError, msg::InstantiateMsg, schema::EntitySchema};
+use cosmwasm_std::Addr;
+use cw_multi_test::{App, Executor};
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "users", "properties": [
+        {"name": "name", "indexed": true, "index_transforms": ["trim", "lowercase"], "value": {"string": {}}},
+        {"name": "title", "indexed": true, "index_transforms": ["normalize"], "value": {"string": {}}},
+        {"name": "bio", "indexed": true, "index_transforms": ["length"], "value": {"string": {}}},
+        {"name": "roles", "indexed": true, "index_transforms": ["length"], "value": {"array": {}}},
+    ]})
+}
+
+fn read(
+    app: &App,
+    addr: &Addr,
+    target: Value,
+) -> Vec<u64> {
+    read_ids_of(app, addr, json!({ "target": target }))
+}
+
+#[test]
+fn queries_apply_the_same_transforms() {
+    let (mut app, addr) = setup(schema(), None);
+    create(
+        &mut app,
+        &addr,
+        1,
+        json!({"name": " Alice", "title": "ｆｕｌｌ", "bio": "hi", "roles": ["a", "b"]}),
+    );
+    create(
+        &mut app,
+        &addr,
+        2,
+        json!({"name": "alicia ", "title": "full", "bio": "hello", "roles": []}),
+    );
+
+    assert_eq!(
+        read(&app, &addr, json!({"equals": {"property": "name", "value": "ALICE  "}})),
+        vec![1]
+    );
+    assert_eq!(
+        read(&app, &addr, json!({"prefix": {"property": "name", "prefix": " ALI"}})),
+        vec![1, 2]
+    );
+    assert_eq!(
+        read(&app, &addr, json!({"equals": {"property": "title", "value": "full"}})),
+        vec![1, 2]
+    );
+    // Length indexes are queried by length
+    assert_eq!(
+        read(
+            &app,
+            &addr,
+            json!({"range": {"property": "bio", "start": {"inclusive": 3}}})
+        ),
+        vec![2]
+    );
+    assert_eq!(
+        read(&app, &addr, json!({"equals": {"property": "roles", "value": 2}})),
+        vec![1]
+    );
+    assert_eq!(
+        read(
+            &app,
+            &addr,
+            json!({"range": {"property": "roles", "stop": {"exclusive": 1}}})
+        ),
+        vec![2]
+    );
+}
+
+#[test]
+fn transforms_must_fit_the_property() {
+    let mut app = App::default();
+    let code_id = store_code(&mut app);
+    for prop in [
+        json!({"name": "n", "index_transforms": ["lowercase"], "value": {"u32": {}}}),
+        json!({"name": "s", "index_transforms": ["length", "trim"], "value": {"string": {}}}),
+    ] {
+        let schema: EntitySchema = serde_json::from_value(json!({"name": "x", "properties": [prop]})).unwrap();
+        let msg = InstantiateMsg {
+            schema,
+            operator: None,
+            metadata: None,
+            config: None,
+        };
+        let result = app.instantiate_contract(code_id, Addr::unchecked(OPERATOR), &msg, &[], "x", None);
+        assert!(result.is_err(), "{}", prop);
+    }
+
+    let (mut app, addr) = setup(schema(), None);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [
+            {"set_index_transforms": {"name": "bio", "transforms": ["length", "lowercase"]}},
+        ]}}),
+    );
+    assert!(matches!(err, ContractError::ValidationError { .. }));
+}
+
+#[test]
+fn changed_transforms_rewrite_the_index() {
+    let schema = json!({"name": "users", "properties": [
+        {"name": "name", "indexed": true, "value": {"string": {}}},
+    ]});
+    let (mut app, addr) = setup(schema, None);
+    create(&mut app, &addr, 1, json!({"name": "Alice"}));
+    assert_eq!(
+        read(&app, &addr, json!({"equals": {"property": "name", "value": "alice"}})),
+        Vec::<u64>::new()
+    );
+
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"alter_schema": {"operations": [
+            {"set_index_transforms": {"name": "name", "transforms": ["lowercase"]}},
+        ]}}),
+    )
+    .unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"continue_job": {}})).unwrap();
+    assert_eq!(
+        read(&app, &addr, json!({"equals": {"property": "name", "value": "ALICE"}})),
+        vec![1]
+    );
+}
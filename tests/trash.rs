@@ -0,0 +1,63 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids, read_ids_of, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({"name": "trash", "properties": [{"name": "title", "indexed": true, "value": {"string": {}}}]})
+}
+
+#[test]
+fn restore_brings_entity_back() {
+    let (mut app, addr) = setup(schema(), Some(json!({"soft_delete": true})));
+    create(&mut app, &addr, 1, json!({"title": "a"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    assert!(read_ids(&app, &addr, &[1]).entities.is_empty());
+    exec(&mut app, &addr, OPERATOR, json!({"restore": {"id": "1"}})).unwrap();
+    assert_eq!(
+        read_ids(&app, &addr, &[1]).entities[0].data,
+        Some(json!({"title": "a"}))
+    );
+}
+
+#[test]
+fn purge_removes_entities_past_retention() {
+    let (mut app, addr) = setup(schema(), Some(json!({"soft_delete": true, "trash_retention": 100})));
+    create(&mut app, &addr, 1, json!({"title": "a"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"purge": {}})).unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"restore": {"id": "1"}})).unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(101));
+    exec(&mut app, &addr, OPERATOR, json!({"purge": {}})).unwrap();
+    let err = exec_err(&mut app, &addr, OPERATOR, json!({"restore": {"id": "1"}}));
+    assert!(matches!(err, ContractError::NotFound { .. }));
+}
+
+#[test]
+fn purge_with_retention_past_epoch_keeps_trash() {
+    let retention = 100 * 365 * 24 * 60 * 60u64;
+    let (mut app, addr) = setup(
+        schema(),
+        Some(json!({"soft_delete": true, "trash_retention": retention})),
+    );
+    create(&mut app, &addr, 1, json!({"title": "a"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"purge": {}})).unwrap();
+    exec(&mut app, &addr, OPERATOR, json!({"restore": {"id": "1"}})).unwrap();
+}
+
+#[test]
+fn ids_target_hides_trashed_and_missing_entities() {
+    let (mut app, addr) = setup(schema(), Some(json!({"soft_delete": true})));
+    create(&mut app, &addr, 1, json!({"title": "a"}));
+    create(&mut app, &addr, 2, json!({"title": "b"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    assert_eq!(
+        read_ids_of(&app, &addr, json!({"target": {"ids": ["1", "2", "3"]}})),
+        vec![2]
+    );
+    let entities = read_ids(&app, &addr, &[1, 2, 3]).entities;
+    assert_eq!(entities.iter().map(|e| e.id.u64()).collect::<Vec<_>>(), vec![2]);
+}
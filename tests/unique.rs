@@ -0,0 +1,84 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids_of, setup, OPERATOR};
+use contract::error::ContractError;
+use cosmwasm_std::Addr;
+use cw_multi_test::App;
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({"name": "users", "properties": [
+        {"name": "email", "unique": true, "value": {"string": {}}},
+        {"name": "handle", "unique": true, "value": {"string": {}}},
+    ]})
+}
+
+fn by_email(
+    app: &App,
+    addr: &Addr,
+    email: &str,
+) -> Vec<u64> {
+    read_ids_of(
+        app,
+        addr,
+        json!({"target": {"unique": {"property": "email", "value": email}}}),
+    )
+}
+
+#[test]
+fn duplicates_name_the_entity_holding_the_value() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"email": "a@x", "handle": "a"}));
+    create(&mut app, &addr, 2, json!({"email": "b@x"}));
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "3", "data": {"email": "a@x", "handle": "a"}}}),
+    );
+    let ContractError::AlreadyExists { reason } = err else {
+        panic!("expected a conflict, got {:?}", err);
+    };
+    assert!(reason.contains("email") && reason.contains("handle") && reason.contains("entity 1"));
+
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "2", "data": {"email": "a@x"}, "mode": "merge"}}),
+    );
+    assert!(matches!(err, ContractError::AlreadyExists { .. }));
+    // Keeping its own value isn't a conflict
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"email": "a@x", "handle": "aa"}, "mode": "replace"}}),
+    )
+    .unwrap();
+}
+
+#[test]
+fn unique_lookup_follows_writes() {
+    let (mut app, addr) = setup(schema(), None);
+    create(&mut app, &addr, 1, json!({"email": "a@x"}));
+    assert_eq!(by_email(&app, &addr, "a@x"), vec![1]);
+    assert_eq!(by_email(&app, &addr, "b@x"), Vec::<u64>::new());
+
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"email": "b@x"}, "mode": "merge"}}),
+    )
+    .unwrap();
+    assert_eq!(by_email(&app, &addr, "a@x"), Vec::<u64>::new());
+    assert_eq!(by_email(&app, &addr, "b@x"), vec![1]);
+
+    // The old value is free for others, and so is the new one after a delete
+    create(&mut app, &addr, 2, json!({"email": "a@x"}));
+    exec(&mut app, &addr, OPERATOR, json!({"delete": {"id": "1"}})).unwrap();
+    create(&mut app, &addr, 3, json!({"email": "b@x"}));
+    assert_eq!(by_email(&app, &addr, "a@x"), vec![2]);
+    assert_eq!(by_email(&app, &addr, "b@x"), vec![3]);
+}
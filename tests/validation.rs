@@ -0,0 +1,85 @@
+mod common;
+
+use common::{create, exec, exec_err, read_ids, setup, OPERATOR};
+use contract::error::ContractError;
+use serde_json::{json, Value};
+
+fn schema(unknown_properties: &str) -> Value {
+    json!({"name": "people", "unknown_properties": unknown_properties, "max_byte_size": 60, "properties": [
+        {"name": "name", "required": true, "value": {"string": {}}},
+        {"name": "age", "value": {"u8": {}}},
+    ]})
+}
+
+fn validation_reason(err: ContractError) -> String {
+    match err {
+        ContractError::ValidationError { reason } => reason,
+        err => panic!("expected a validation error, got {:?}", err),
+    }
+}
+
+#[test]
+fn create_lists_every_offending_property() {
+    let (mut app, addr) = setup(schema("reject"), None);
+    let err = exec_err(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"create": {"id": "1", "data": {"age": 300, "extra": true}}}),
+    );
+    let reason = validation_reason(err);
+    for name in ["name", "age", "extra"] {
+        assert!(reason.contains(name), "{} missing from {}", name, reason);
+    }
+}
+
+#[test]
+fn unknown_properties_follow_the_policy() {
+    let (mut app, addr) = setup(schema("strip"), None);
+    create(&mut app, &addr, 1, json!({"name": "a", "extra": true}));
+    assert_eq!(read_ids(&app, &addr, &[1]).entities[0].data, Some(json!({"name": "a"})));
+
+    let (mut app, addr) = setup(schema("allow"), None);
+    create(&mut app, &addr, 1, json!({"name": "a", "extra": true}));
+    assert_eq!(
+        read_ids(&app, &addr, &[1]).entities[0].data,
+        Some(json!({"name": "a", "extra": true}))
+    );
+}
+
+#[test]
+fn updates_are_validated_in_both_modes() {
+    let (mut app, addr) = setup(schema("reject"), None);
+    create(&mut app, &addr, 1, json!({"name": "a", "age": 30}));
+    for (mode, data) in [
+        ("merge", json!({"age": "old"})),
+        ("merge", json!({"extra": 1})),
+        ("replace", json!({"age": 31})),
+        ("replace", json!({"name": "x".repeat(60)})),
+    ] {
+        let err = exec_err(
+            &mut app,
+            &addr,
+            OPERATOR,
+            json!({"update": {"id": "1", "data": data, "mode": mode}}),
+        );
+        assert!(
+            matches!(err, ContractError::ValidationError { .. }),
+            "{} {}",
+            mode,
+            data
+        );
+    }
+
+    exec(
+        &mut app,
+        &addr,
+        OPERATOR,
+        json!({"update": {"id": "1", "data": {"age": 31}, "mode": "merge"}}),
+    )
+    .unwrap();
+    assert_eq!(
+        read_ids(&app, &addr, &[1]).entities[0].data,
+        Some(json!({"name": "a", "age": 31}))
+    );
+}